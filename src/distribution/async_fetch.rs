@@ -0,0 +1,215 @@
+use crate::distribution::auth::{self, Authenticate, Credential};
+use crate::distribution::{
+    ManifestResponse, Registry, RegistryApiErrors, RegistryAuth, RegistryError,
+};
+use crate::image::manifest::{Digest, DigestMismatchError, ManifestError};
+
+use reqwest_async::header::{HeaderMap, ACCEPT, CONTENT_TYPE, WWW_AUTHENTICATE};
+use reqwest_async::{Client, Response};
+
+/// Build the async HTTP client used by a registry.
+///
+/// # Panics
+/// Panics if the client cannot be initialized, like [Registry::new].
+pub(crate) fn client() -> Client {
+    Client::builder()
+        .gzip(true)
+        .build()
+        .expect("Could not build async request client")
+}
+
+/// Convert a status code of the async client to the one used in errors.
+pub(crate) fn status_code(status: reqwest_async::StatusCode) -> reqwest::StatusCode {
+    reqwest::StatusCode::from_u16(status.as_u16()).expect("Status code is out of range")
+}
+
+impl RegistryError {
+    pub(crate) fn from_async(error: reqwest_async::Error) -> Self {
+        RegistryError::AsyncRequestError(Box::new(error))
+    }
+
+    /// Create an error for a response with a non-success status, like
+    /// `from_response`.
+    async fn from_async_response(response: Response) -> Self {
+        let status = response.status().as_u16();
+        let url = response.url().to_string();
+        let errors = response
+            .json::<RegistryApiErrors>()
+            .await
+            .map(|body| body.errors)
+            .unwrap_or_default();
+
+        RegistryError::HttpStatus {
+            status,
+            url,
+            errors,
+        }
+    }
+}
+
+impl Registry {
+    async fn attempt_request_async(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        cred: Option<&Credential>,
+    ) -> Result<Response, RegistryError> {
+        let mut request = self.async_client.get(url).headers(headers.clone());
+
+        if let Some(credential) = cred {
+            request = request.authenticate(&credential);
+        } else if self.auth != RegistryAuth::Anonymous {
+            request = request.authenticate_with(&self.auth);
+        } else {
+            info!("Attempting unauthenticated request");
+        }
+
+        let response = request.send().await.map_err(RegistryError::from_async)?;
+
+        info!("got response: {:?}", response);
+
+        if response.status().is_success() && self.served_by_registry(response.url().as_str()) {
+            Self::check_api_version_header(
+                response
+                    .headers()
+                    .get("Docker-Distribution-API-Version")
+                    .map(|version| version.as_bytes()),
+            )?;
+        }
+
+        Ok(response)
+    }
+
+    /// Perform a GET request on the Registry without blocking, handling
+    /// authentication like [Registry::get].
+    async fn get_async(&self, url: &str, headers: HeaderMap) -> Result<Response, RegistryError> {
+        let credential = self.credential_cache.get(url);
+
+        let response = self
+            .attempt_request_async(url, &headers, credential)
+            .await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        if response.status() != reqwest_async::StatusCode::UNAUTHORIZED {
+            return Err(RegistryError::from_async_response(response).await);
+        }
+
+        info!("Authentication required");
+        let authenticate = response
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .ok_or_else(|| {
+                RegistryError::InvalidAuthenticationChallenge(
+                    "No authentication challenge presented".into(),
+                )
+            })?
+            .as_bytes()
+            .to_vec();
+
+        let credentials = auth::do_challenge_async(
+            &self.async_client,
+            &authenticate,
+            self.credentials().as_ref(),
+        )
+        .await?;
+
+        // Attempt with each credential we got
+        for credential in credentials {
+            let response = self
+                .attempt_request_async(url, &headers, Some(&credential))
+                .await?;
+            if response.status().is_success() {
+                return Ok(response);
+            }
+        }
+
+        Err(RegistryError::CouldNotAuthenticate)
+    }
+
+    /// Fetch a manifest along with its media type, digest and size, without
+    /// blocking.
+    ///
+    /// This is the async variant of [Registry::get_manifest_with_content_type].
+    /// Mirrors and the disk cache are not used.
+    pub async fn get_manifest_async(
+        &self,
+        image_name: &str,
+        reference: &str,
+    ) -> Result<ManifestResponse, RegistryError> {
+        let url = format!("{}/v2/{}/manifests/{}", self.url, image_name, reference);
+
+        let mut headers = HeaderMap::new();
+        let accept = self.manifest_media_types.join(",");
+        headers.insert(
+            ACCEPT,
+            accept.parse().map_err(|_| {
+                RegistryError::ManifestError(ManifestError::InvalidMediaType(accept.clone()))
+            })?,
+        );
+
+        let response = self.get_async(&url, headers).await?;
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .ok_or_else(|| RegistryError::InvalidResponse("Missing Content-Type header".into()))?
+            .to_str()
+            .map_err(|e| RegistryError::InvalidResponse(format!("Invalid Content-Type: {}", e)))?
+            .to_owned();
+
+        let header_digest = match response.headers().get("Docker-Content-Digest") {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .map_err(|e| {
+                        RegistryError::InvalidResponse(format!(
+                            "Invalid Docker-Content-Digest: {}",
+                            e
+                        ))
+                    })?
+                    .parse::<Digest>()
+                    .map_err(RegistryError::ManifestError)?,
+            ),
+            None => None,
+        };
+
+        let content_length = response.content_length();
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(RegistryError::from_async)?
+            .to_vec();
+
+        let digest = Self::check_manifest_digest(&body, header_digest)?;
+        let size = content_length.map_or(body.len(), |length| length as usize);
+
+        Self::parse_manifest_response(body, content_type, digest, size)
+    }
+
+    /// Fetch a blob without blocking, verifying it against its digest.
+    ///
+    /// The blob is held in memory as a whole. Mirrors and the disk cache are
+    /// not used.
+    pub(crate) async fn fetch_blob_async(
+        &self,
+        name: &str,
+        digest: &Digest,
+    ) -> Result<Vec<u8>, RegistryError> {
+        let url = format!("{}/v2/{}/blobs/{}", self.url, name, digest);
+
+        let blob = self
+            .get_async(&url, HeaderMap::new())
+            .await?
+            .bytes()
+            .await
+            .map_err(RegistryError::from_async)?
+            .to_vec();
+
+        DigestMismatchError::check(digest, &blob, "blob")?;
+
+        Ok(blob)
+    }
+}
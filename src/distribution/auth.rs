@@ -12,8 +12,144 @@ pub enum Credential {
     Token(Token),
 }
 
+/// Username and password used to authenticate against a registry.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        Credentials {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Credentials {{ username: {:?}, password: <redacted> }}",
+            self.username
+        )
+    }
+}
+
+/// Authentication attached to every request sent to a registry.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RegistryAuth {
+    /// Send requests without an `Authorization` header.
+    Anonymous,
+
+    /// Use HTTP Basic authentication.
+    Basic { username: String, password: String },
+
+    /// Use a pre-obtained bearer token.
+    Bearer(String),
+
+    /// Answer `WWW-Authenticate: Bearer` challenges by fetching a token from
+    /// the token server named in the challenge, as described in the [Docker
+    /// Token Authentication
+    /// Specification](https://docs.docker.com/registry/spec/auth/token/).
+    ///
+    /// The credentials, if any, are only presented to the token server.
+    DockerTokenService(Option<Credentials>),
+}
+
+impl Default for RegistryAuth {
+    fn default() -> Self {
+        RegistryAuth::Anonymous
+    }
+}
+
+impl fmt::Debug for RegistryAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryAuth::Anonymous => write!(f, "Anonymous"),
+            RegistryAuth::Basic { username, .. } => write!(
+                f,
+                "Basic {{ username: {:?}, password: <redacted> }}",
+                username
+            ),
+            RegistryAuth::Bearer(_) => write!(f, "Bearer(<redacted>)"),
+            RegistryAuth::DockerTokenService(credentials) => {
+                write!(f, "DockerTokenService({:?})", credentials)
+            }
+        }
+    }
+}
+
+/// A source of credentials for registries.
+///
+/// Implement this to integrate with external credential providers, such as
+/// OS credential managers or secret stores.
+pub trait CredentialStore: Send + Sync {
+    /// Return the credentials to use for the registry at `registry_url`, if any.
+    fn get_credentials(&self, registry_url: &str) -> Option<Credentials>;
+}
+
+/// Credential store reading credentials from the `<PREFIX>_USERNAME` and
+/// `<PREFIX>_PASSWORD` environment variables.
+///
+/// The default prefix is `REGISTRY`, i.e. the `REGISTRY_USERNAME` and
+/// `REGISTRY_PASSWORD` variables are read. The same credentials are returned
+/// for every registry. If either variable is unset, no credentials are
+/// returned.
+#[derive(Debug)]
+pub struct EnvCredentialStore {
+    prefix: String,
+}
+
+impl EnvCredentialStore {
+    /// Create a credential store reading the `<prefix>_USERNAME` and
+    /// `<prefix>_PASSWORD` environment variables.
+    pub fn with_prefix(prefix: &str) -> Self {
+        EnvCredentialStore {
+            prefix: prefix.to_owned(),
+        }
+    }
+}
+
+impl Default for EnvCredentialStore {
+    fn default() -> Self {
+        Self::with_prefix("REGISTRY")
+    }
+}
+
+impl CredentialStore for EnvCredentialStore {
+    fn get_credentials(&self, _registry_url: &str) -> Option<Credentials> {
+        let username = std::env::var(format!("{}_USERNAME", self.prefix)).ok()?;
+        let password = std::env::var(format!("{}_PASSWORD", self.prefix)).ok()?;
+
+        Some(Credentials { username, password })
+    }
+}
+
+/// Credential store returning a fixed set of credentials for every registry.
+#[derive(Debug)]
+pub struct StaticCredentialStore {
+    credentials: Credentials,
+}
+
+impl StaticCredentialStore {
+    pub fn new(credentials: Credentials) -> Self {
+        StaticCredentialStore { credentials }
+    }
+}
+
+impl CredentialStore for StaticCredentialStore {
+    fn get_credentials(&self, _registry_url: &str) -> Option<Credentials> {
+        Some(self.credentials.clone())
+    }
+}
+
 pub trait Authenticate {
     fn authenticate(self, auth: &Credential) -> Self;
+
+    fn authenticate_with(self, auth: &RegistryAuth) -> Self;
 }
 
 impl Authenticate for reqwest::RequestBuilder {
@@ -22,6 +158,35 @@ impl Authenticate for reqwest::RequestBuilder {
             Credential::Token(t) => self.bearer_auth(t),
         }
     }
+
+    fn authenticate_with(self, auth: &RegistryAuth) -> Self {
+        match auth {
+            RegistryAuth::Anonymous => self,
+            RegistryAuth::Basic { username, password } => self.basic_auth(username, Some(password)),
+            RegistryAuth::Bearer(token) => self.bearer_auth(token),
+            // The token is only known after answering a challenge.
+            RegistryAuth::DockerTokenService(_) => self,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Authenticate for reqwest_async::RequestBuilder {
+    fn authenticate(self, auth: &Credential) -> Self {
+        match auth {
+            Credential::Token(t) => self.bearer_auth(t),
+        }
+    }
+
+    fn authenticate_with(self, auth: &RegistryAuth) -> Self {
+        match auth {
+            RegistryAuth::Anonymous => self,
+            RegistryAuth::Basic { username, password } => self.basic_auth(username, Some(password)),
+            RegistryAuth::Bearer(token) => self.bearer_auth(token),
+            // The token is only known after answering a challenge.
+            RegistryAuth::DockerTokenService(_) => self,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -76,6 +241,31 @@ impl www_authenticate::Challenge for BearerChallenge {
     }
 }
 
+impl BearerChallenge {
+    /// Return the URL of the token server.
+    fn realm(&self) -> Result<&str, RegistryError> {
+        self.realm.as_ref().map(String::as_str).ok_or_else(|| {
+            RegistryError::InvalidAuthenticationChallenge("No Realm provided".into())
+        })
+    }
+
+    /// Return the query parameters of the token request.
+    fn query_params(&self) -> Vec<(&str, &str)> {
+        let mut query_params: Vec<(&str, &str)> = self
+            .scopes
+            .iter()
+            .flat_map(|some| some.iter())
+            .map(|scope| ("scope", scope.as_str()))
+            .collect();
+
+        if let Some(ref service) = self.service {
+            query_params.push(("service", &service));
+        }
+
+        query_params
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct Token {
     // FIXME: allow accesss_token here.
@@ -93,33 +283,19 @@ pub struct Token {
 }
 
 impl Token {
-    fn get(client: &Client, chall: &BearerChallenge) -> Result<Token, RegistryError> {
-        #[allow(clippy::or_fun_call)]
-        let realm = chall
-            .realm
-            .clone()
-            .ok_or(RegistryError::InvalidAuthenticationChallenge(
-                "No Realm provided".into(),
-            ))?;
-
-        let request = client.get(&realm);
-
-        let mut query_params: Vec<(&str, &str)> = vec![];
+    fn get(
+        client: &Client,
+        chall: &BearerChallenge,
+        credentials: Option<&Credentials>,
+    ) -> Result<Token, RegistryError> {
+        let mut request = client.get(chall.realm()?);
 
-        let mut scopes: Vec<(&str, &str)> = chall
-            .scopes
-            .iter()
-            .flat_map(|some| some.iter())
-            .map(|scope| ("scope", scope.as_str()))
-            .collect();
-
-        query_params.append(&mut scopes);
-
-        if let Some(ref service) = chall.service {
-            query_params.push(("service", &service));
+        // The token server authenticates the client using basic auth.
+        if let Some(credentials) = credentials {
+            request = request.basic_auth(&credentials.username, Some(&credentials.password));
         }
 
-        let request = request.query(&query_params);
+        let request = request.query(&chall.query_params());
 
         let mut response = request.send().map_err(RegistryError::ReqwestError)?;
 
@@ -132,6 +308,35 @@ impl Token {
 
         Ok(token)
     }
+
+    #[cfg(feature = "async")]
+    async fn get_async(
+        client: &reqwest_async::Client,
+        chall: &BearerChallenge,
+        credentials: Option<&Credentials>,
+    ) -> Result<Token, RegistryError> {
+        let mut request = client.get(chall.realm()?);
+
+        // The token server authenticates the client using basic auth.
+        if let Some(credentials) = credentials {
+            request = request.basic_auth(&credentials.username, Some(&credentials.password));
+        }
+
+        let response = request
+            .query(&chall.query_params())
+            .send()
+            .await
+            .map_err(RegistryError::from_async)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RegistryError::CouldNotGetToken(
+                super::async_fetch::status_code(status),
+            ));
+        }
+
+        response.json().await.map_err(RegistryError::from_async)
+    }
 }
 
 impl fmt::Display for Token {
@@ -140,23 +345,33 @@ impl fmt::Display for Token {
     }
 }
 
-pub fn do_challenge(
-    client: &Client,
-    authenticate: &reqwest::header::HeaderValue,
-) -> Result<Vec<Credential>, RegistryError> {
-    let raw: hyperx::header::Raw = authenticate.as_bytes().into();
+/// Parse the bearer challenges of a `WWW-Authenticate` header.
+fn parse_challenges(authenticate: &[u8]) -> Result<Vec<BearerChallenge>, RegistryError> {
+    let raw: hyperx::header::Raw = authenticate.into();
 
     #[allow(clippy::or_fun_call)]
-    let challenges = WwwAuthenticate::parse_header(&raw)
-        .map_err(|_| RegistryError::InvalidAuthenticationChallenge(format!("{:?}", authenticate)))?
+    WwwAuthenticate::parse_header(&raw)
+        .map_err(|_| {
+            RegistryError::InvalidAuthenticationChallenge(
+                String::from_utf8_lossy(authenticate).into_owned(),
+            )
+        })?
         .get::<BearerChallenge>()
         .ok_or(RegistryError::InvalidAuthenticationChallenge(
             "No Bearer Challenge provided".into(),
-        ))?;
+        ))
+}
+
+pub fn do_challenge(
+    client: &Client,
+    authenticate: &reqwest::header::HeaderValue,
+    credentials: Option<&Credentials>,
+) -> Result<Vec<Credential>, RegistryError> {
+    let challenges = parse_challenges(authenticate.as_bytes())?;
 
     let auths: Vec<Credential> = challenges
         .iter()
-        .map(|c| Token::get(&client, c))
+        .map(|c| Token::get(&client, c, credentials))
         .filter_map(Result::ok)
         .map(Credential::Token)
         .collect();
@@ -165,3 +380,96 @@ pub fn do_challenge(
 
     Ok(auths)
 }
+
+/// Answer an authentication challenge like [do_challenge], using the async
+/// client.
+#[cfg(feature = "async")]
+pub async fn do_challenge_async(
+    client: &reqwest_async::Client,
+    authenticate: &[u8],
+    credentials: Option<&Credentials>,
+) -> Result<Vec<Credential>, RegistryError> {
+    let mut auths: Vec<Credential> = vec![];
+    for challenge in parse_challenges(authenticate)? {
+        if let Ok(token) = Token::get_async(client, &challenge, credentials).await {
+            auths.push(Credential::Token(token));
+        }
+    }
+
+    info!("got credentials: {:?}", auths);
+
+    Ok(auths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockCredentialStore {
+        calls: AtomicUsize,
+    }
+
+    impl CredentialStore for MockCredentialStore {
+        fn get_credentials(&self, registry_url: &str) -> Option<Credentials> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            if registry_url == "https://registry.example.com" {
+                Some(Credentials::new("mock", "secret"))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_mock_credential_store() {
+        let store = MockCredentialStore {
+            calls: AtomicUsize::new(0),
+        };
+
+        assert_eq!(
+            store.get_credentials("https://registry.example.com"),
+            Some(Credentials::new("mock", "secret"))
+        );
+        assert_eq!(store.get_credentials("https://other.example.com"), None);
+        assert_eq!(store.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_static_credential_store() {
+        let store = StaticCredentialStore::new(Credentials::new("user", "pass"));
+
+        assert_eq!(
+            store.get_credentials("https://registry.example.com"),
+            Some(Credentials::new("user", "pass"))
+        );
+    }
+
+    #[test]
+    fn test_env_credential_store() {
+        assert_eq!(EnvCredentialStore::default().prefix, "REGISTRY");
+
+        std::env::set_var("TEST_ENV_CREDENTIAL_STORE_USERNAME", "envuser");
+        std::env::set_var("TEST_ENV_CREDENTIAL_STORE_PASSWORD", "envpass");
+
+        let store = EnvCredentialStore::with_prefix("TEST_ENV_CREDENTIAL_STORE");
+        assert_eq!(
+            store.get_credentials("https://registry.example.com"),
+            Some(Credentials::new("envuser", "envpass"))
+        );
+
+        std::env::remove_var("TEST_ENV_CREDENTIAL_STORE_PASSWORD");
+        assert_eq!(store.get_credentials("https://registry.example.com"), None);
+
+        std::env::remove_var("TEST_ENV_CREDENTIAL_STORE_USERNAME");
+    }
+
+    #[test]
+    fn test_credentials_debug_redacts_password() {
+        let debug = format!("{:?}", Credentials::new("user", "hunter2"));
+
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
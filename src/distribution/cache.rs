@@ -0,0 +1,256 @@
+use crate::distribution::RegistryError;
+use crate::image::manifest::{Digest, DigestAlgorithm, DigestMismatchError};
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Persistent on-disk cache for blobs and manifests.
+///
+/// Blobs are stored as `{root}/blobs/{algorithm}/{hex}`, manifests as
+/// `{root}/manifests/{registry_host}/{name}/{reference}` with their media type
+/// stored next to them in a `.content-type` file.
+#[derive(Debug)]
+pub struct DiskCache {
+    root: PathBuf,
+}
+
+/// Make a string usable as a single path component on all platforms.
+fn sanitize(component: &str) -> String {
+    component.replace(':', "_").replace('/', "_")
+}
+
+/// Return whether `name` is a valid repository name.
+///
+/// The [distribution
+/// spec](https://github.com/opencontainers/distribution-spec/blob/master/spec.md#pulling-manifests)
+/// defines names as `/`-separated components of lowercase alphanumerics,
+/// joined by `.`, `_`, `__` or any number of `-`. In particular, no
+/// component can be `.` or `..`.
+fn is_valid_name(name: &str) -> bool {
+    name.split('/').all(|component| {
+        let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+        component.starts_with(is_alphanumeric)
+            && component.ends_with(is_alphanumeric)
+            && component
+                .split(is_alphanumeric)
+                .filter(|separator| !separator.is_empty())
+                .all(|separator| {
+                    separator == "."
+                        || separator == "_"
+                        || separator == "__"
+                        || separator.chars().all(|c| c == '-')
+                })
+    })
+}
+
+/// Return whether `reference` is a valid tag or digest.
+///
+/// Tags consist of up to 128 alphanumerics, `.`, `_` and `-`, and must not
+/// start with `.` or `-`.
+fn is_valid_reference(reference: &str) -> bool {
+    if reference.parse::<Digest>().is_ok() {
+        return true;
+    }
+
+    let is_tag_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    reference.len() <= 128
+        && reference.starts_with(is_tag_char)
+        && reference
+            .chars()
+            .all(|c| is_tag_char(c) || c == '.' || c == '-')
+}
+
+/// Compute the digest of a file.
+fn hash_file(path: &Path, algorithm: DigestAlgorithm) -> io::Result<Digest> {
+    let mut hasher = algorithm.new_hasher();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+/// Return the combined size of all files below a path.
+fn dir_size(path: &Path) -> io::Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+
+    Ok(size)
+}
+
+impl DiskCache {
+    pub fn new(root: PathBuf) -> Self {
+        DiskCache { root }
+    }
+
+    fn blob_path(&self, digest: &Digest) -> PathBuf {
+        self.root
+            .join("blobs")
+            .join(digest.algorithm.to_string())
+            .join(&digest.hex)
+    }
+
+    /// Return the path of a cached manifest.
+    ///
+    /// Returns `None` if `name` or `reference` are invalid, as they could
+    /// otherwise point outside of the cache.
+    fn manifest_path(&self, registry_url: &str, name: &str, reference: &str) -> Option<PathBuf> {
+        let host = sanitize(
+            registry_url
+                .splitn(2, "://")
+                .last()
+                .unwrap_or(registry_url)
+                .trim_end_matches('/'),
+        );
+
+        if host.is_empty() || host == "." || host == ".." {
+            warn!("Not caching manifests of registry {}", registry_url);
+            return None;
+        }
+
+        if !is_valid_name(name) || !is_valid_reference(reference) {
+            warn!(
+                "Not caching manifest with invalid name {}:{}",
+                name, reference
+            );
+            return None;
+        }
+
+        Some(
+            self.root
+                .join("manifests")
+                .join(host)
+                .join(name)
+                .join(sanitize(reference)),
+        )
+    }
+
+    fn content_type_path(manifest_path: &Path) -> PathBuf {
+        let mut path = manifest_path.as_os_str().to_owned();
+        path.push(".content-type");
+        PathBuf::from(path)
+    }
+
+    /// Open a cached blob.
+    ///
+    /// The cached content is verified against the digest. Corrupted entries
+    /// are removed from the cache and treated as missing.
+    pub fn get_blob(&self, digest: &Digest) -> Result<Option<File>, RegistryError> {
+        let path = self.blob_path(digest);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let actual = hash_file(&path, digest.algorithm).map_err(RegistryError::IoError)?;
+        if &actual != digest {
+            warn!("Removing corrupted cache entry for {}", digest);
+            fs::remove_file(&path).map_err(RegistryError::IoError)?;
+            return Ok(None);
+        }
+
+        info!("Using cached blob {}", digest);
+        File::open(&path).map(Some).map_err(RegistryError::IoError)
+    }
+
+    /// Write a blob to the cache and open the cached copy.
+    ///
+    /// The content is verified against the digest before it is added to the
+    /// cache.
+    pub fn put_blob(&self, digest: &Digest, reader: &mut dyn Read) -> Result<File, RegistryError> {
+        let path = self.blob_path(digest);
+        let partial = path.with_file_name(format!("{}.partial", digest.hex));
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(RegistryError::IoError)?;
+        }
+
+        let mut file = File::create(&partial).map_err(RegistryError::IoError)?;
+        io::copy(reader, &mut file).map_err(RegistryError::IoError)?;
+        drop(file);
+
+        let actual = hash_file(&partial, digest.algorithm).map_err(RegistryError::IoError)?;
+        if let Err(e) = DigestMismatchError::compare(digest, actual, "blob") {
+            fs::remove_file(&partial).map_err(RegistryError::IoError)?;
+            return Err(e.into());
+        }
+
+        fs::rename(&partial, &path).map_err(RegistryError::IoError)?;
+        File::open(&path).map_err(RegistryError::IoError)
+    }
+
+    /// Return a cached manifest and its media type.
+    ///
+    /// If `reference` is a digest, the cached manifest is verified against it.
+    pub fn get_manifest(
+        &self,
+        registry_url: &str,
+        name: &str,
+        reference: &str,
+    ) -> Option<(String, Vec<u8>)> {
+        let path = self.manifest_path(registry_url, name, reference)?;
+
+        let body = fs::read(&path).ok()?;
+        let content_type = fs::read_to_string(Self::content_type_path(&path)).ok()?;
+
+        if let Ok(digest) = reference.parse::<Digest>() {
+            if Digest::compute(digest.algorithm, &body) != digest {
+                warn!("Ignoring corrupted cached manifest {}", digest);
+                return None;
+            }
+        }
+
+        info!("Using cached manifest {}:{}", name, reference);
+        Some((content_type, body))
+    }
+
+    /// Write a manifest and its media type to the cache.
+    ///
+    /// Manifests with an invalid name or reference are not cached.
+    pub fn put_manifest(
+        &self,
+        registry_url: &str,
+        name: &str,
+        reference: &str,
+        content_type: &str,
+        body: &[u8],
+    ) -> Result<(), RegistryError> {
+        let path = match self.manifest_path(registry_url, name, reference) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(RegistryError::IoError)?;
+        }
+
+        fs::write(&path, body).map_err(RegistryError::IoError)?;
+        fs::write(Self::content_type_path(&path), content_type).map_err(RegistryError::IoError)
+    }
+
+    /// Remove all cached content.
+    pub fn clear(&self) -> io::Result<()> {
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the combined size of all cached content in bytes.
+    pub fn size_bytes(&self) -> io::Result<u64> {
+        dir_size(&self.root)
+    }
+}
@@ -1,34 +1,256 @@
+#[cfg(feature = "async")]
+mod async_fetch;
 mod auth;
 use auth::{Authenticate, Credential};
+mod cache;
+mod upload;
+pub use auth::{
+    CredentialStore, Credentials, EnvCredentialStore, RegistryAuth, StaticCredentialStore,
+};
+use cache::DiskCache;
+pub use upload::UploadSession;
 
-use crate::image::Image;
+use crate::image::manifest::{
+    Digest, DigestAlgorithm, DigestMismatchError, ManifestError, ManifestV2_2, VerifiedReader,
+};
+use crate::image::{Image, ManifestV2};
 
-use reqwest::{Client, StatusCode};
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
 use ttl_cache::TtlCache;
 
-#[derive(Debug, Fail)]
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
 pub enum RegistryError {
-    #[fail(display = "Request Error: {:?}", _0)]
-    ReqwestError(#[cause] reqwest::Error),
+    #[error("Request Error: {0:?}")]
+    ReqwestError(#[source] reqwest::Error),
+
+    /// An error of the async HTTP client, only returned with the `async`
+    /// feature.
+    #[error("Request Error: {0}")]
+    AsyncRequestError(#[source] Box<dyn std::error::Error + Send + Sync>),
 
-    #[fail(display = "Invalid authentication challenge: {}", _0)]
+    #[error("Invalid authentication challenge: {0}")]
     InvalidAuthenticationChallenge(String),
 
-    #[fail(display = "Could not get token: {}", _0)]
+    #[error("Could not get token: {0}")]
     CouldNotGetToken(StatusCode),
 
-    #[fail(display = "Could not authenticate")]
+    #[error("Could not authenticate")]
     CouldNotAuthenticate,
 
-    #[fail(display = "Manifest Error: {:?}", _0)]
-    ManifestError(#[cause] crate::image::manifest::ManifestError),
+    #[error("Manifest Error: {0:?}")]
+    ManifestError(#[source] crate::image::manifest::ManifestError),
 
-    #[fail(display = "Unsupported Manifest Schema: {:?}", _0)]
+    #[error("Unsupported Manifest Schema: {0:?}")]
     UnsupportedManifestSchema(crate::image::manifest::ManifestV2Schema),
 
-    #[fail(display = "Image Spec Error: {:?}", _0)]
-    ImageSpecError(#[cause] crate::image::spec::ImageSpecError),
+    #[error("Image Spec Error: {0:?}")]
+    ImageSpecError(#[source] crate::image::spec::ImageSpecError),
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Unsupported registry API version: {got}")]
+    ApiVersionMismatch { got: String },
+
+    #[error("Missing Docker-Distribution-API-Version header")]
+    MissingApiVersionHeader,
+
+    #[error("I/O Error: {0:?}")]
+    IoError(#[source] std::io::Error),
+
+    #[error("{0}")]
+    DigestMismatch(#[from] DigestMismatchError),
+
+    #[error("Content Store Error: {0}")]
+    StoreError(#[source] crate::store::StoreError),
+
+    #[error("Invalid tag list: {0}")]
+    TagListError(#[source] serde_json::Error),
+
+    #[error("Upload failed: {0}")]
+    UploadFailed(StatusCode),
+
+    #[error("HTTP {status} for {url}: {errors:?}")]
+    HttpStatus {
+        status: u16,
+        url: String,
+        errors: Vec<RegistryApiError>,
+    },
+}
+
+/// An error reported by the registry in the body of an error response.
+///
+/// See [Error
+/// Codes](https://github.com/opencontainers/distribution-spec/blob/master/spec.md#error-codes)
+/// in the distribution spec.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RegistryApiError {
+    /// The error code, e.g. `MANIFEST_UNKNOWN`.
+    pub code: String,
+
+    /// A human readable description of the error.
+    #[serde(default)]
+    pub message: String,
+
+    /// Unstructured additional information about the error.
+    pub detail: Option<serde_json::Value>,
+}
+
+impl std::fmt::Display for RegistryApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+/// Body of an error response.
+#[derive(Debug, Deserialize)]
+struct RegistryApiErrors {
+    errors: Vec<RegistryApiError>,
+}
+
+impl RegistryError {
+    /// Create an error for a response with a non-success status.
+    ///
+    /// The errors in the response body are included if the body can be
+    /// parsed.
+    fn from_response(mut response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let url = response.url().to_string();
+        let errors = response
+            .json::<RegistryApiErrors>()
+            .map(|body| body.errors)
+            .unwrap_or_default();
+
+        RegistryError::HttpStatus {
+            status,
+            url,
+            errors,
+        }
+    }
+}
+
+/// Value of the `Docker-Distribution-API-Version` header sent by registries
+/// implementing the V2 API.
+const API_VERSION: &str = "registry/2.0";
+
+/// Manifest media types accepted when fetching manifests.
+///
+/// Make sure we only accept schema 2, if we don't set this, we will get
+/// schema1 by default.
+const MANIFEST_ACCEPT_TYPES: &[&str] = &[
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+/// Reader verifying its content against a digest at the end of the content.
+///
+/// Unlike [VerifiedReader], which leaves it to the caller to check the digest,
+/// reading fails with [std::io::ErrorKind::InvalidData] on a mismatch.
+struct CheckedReader<R> {
+    /// The verifying reader, until the end of the content has been reached.
+    inner: Option<VerifiedReader<R>>,
+}
+
+impl<R: Read> Read for CheckedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = match self.inner {
+            Some(ref mut reader) => reader.read(buf)?,
+            None => return Ok(0),
+        };
+
+        if n == 0 && !buf.is_empty() {
+            if let Some(reader) = self.inner.take() {
+                reader
+                    .finish()
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+/// Response of the tag listing endpoint.
+#[derive(Debug, Deserialize)]
+struct TagList {
+    tags: Option<Vec<String>>,
+}
+
+/// Response of the catalog endpoint.
+#[derive(Debug, Deserialize)]
+struct Catalog {
+    repositories: Option<Vec<String>>,
+}
+
+/// Match `text` against a glob-style `pattern`.
+///
+/// `*` matches any sequence of characters, `?` matches any single character.
+/// All other characters match themselves.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it was
+    // tried at, for backtracking.
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // Let the last `*` match one more character.
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A manifest together with the metadata of the response it was served with.
+#[derive(Debug)]
+pub struct ManifestResponse {
+    /// The parsed manifest.
+    pub manifest: ManifestV2,
+
+    /// The media type of the manifest, as given by the `Content-Type` header.
+    pub content_type: String,
+
+    /// The digest of the manifest, as given by the `Docker-Content-Digest`
+    /// header. If the registry did not send this header, the SHA-256 digest of
+    /// the response body is used.
+    pub digest: Digest,
+
+    /// The size of the manifest in bytes, as given by the `Content-Length`
+    /// header, or the length of the response body if the header is missing.
+    pub size: usize,
+}
+
+/// Information about a blob, as returned by [Registry::head_blob].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlobInfo {
+    /// The size of the blob in bytes.
+    pub size: u64,
+
+    /// The media type the blob is served with.
+    pub content_type: String,
 }
 
 /// Represents a Registry implementing the [OpenContainer Distribution
@@ -37,6 +259,13 @@ pub struct Registry {
     pub url: String,
     client: Client,
     credential_cache: TtlCache<String, Credential>,
+    credential_store: Option<Arc<dyn CredentialStore>>,
+    auth: RegistryAuth,
+    cache: Option<DiskCache>,
+    mirrors: Vec<String>,
+    manifest_media_types: Vec<String>,
+    #[cfg(feature = "async")]
+    async_client: reqwest_async::Client,
 }
 
 impl std::fmt::Debug for Registry {
@@ -79,30 +308,208 @@ impl Registry {
             url: url.into(),
             client,
             credential_cache,
+            credential_store: None,
+            auth: RegistryAuth::Anonymous,
+            cache: None,
+            mirrors: vec![],
+            manifest_media_types: MANIFEST_ACCEPT_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            #[cfg(feature = "async")]
+            async_client: async_fetch::client(),
+        }
+    }
+
+    /// Set the manifest media types to accept, in order of preference.
+    ///
+    /// The media types are sent in the `Accept` header when fetching
+    /// manifests, which registries use to decide which manifest format to
+    /// return. By default, both OCI and Docker manifests and manifest lists
+    /// are accepted, preferring OCI image manifests. An empty list restores
+    /// the default, as registries would otherwise fall back to schema 1.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    /// let registry = Registry::new("https://registry-1.docker.io").with_media_type_preference(&[
+    ///     "application/vnd.oci.image.manifest.v1+json",
+    ///     "application/vnd.oci.image.index.v1+json",
+    /// ]);
+    /// ```
+    pub fn with_media_type_preference(mut self, prefs: &[&str]) -> Self {
+        let prefs = if prefs.is_empty() {
+            MANIFEST_ACCEPT_TYPES
+        } else {
+            prefs
+        };
+        self.manifest_media_types = prefs.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with custom timeouts, proxies or
+    /// root certificates.
+    ///
+    /// All requests of a registry go through the same client, so connections
+    /// are pooled and kept alive across requests. As clients share their pool
+    /// when cloned, passing a clone of the same client to several registries
+    /// shares connections between them as well.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# extern crate reqwest;
+    ///# use opencontainers::Registry;
+    /// use std::time::Duration;
+    ///
+    /// let client = reqwest::Client::builder()
+    ///     .timeout(Duration::from_secs(30))
+    ///     .build()
+    ///     .expect("Could not build client");
+    /// let registry = Registry::new("https://registry-1.docker.io").with_client(client);
+    /// ```
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Cache blobs and manifests fetched from this registry on disk.
+    ///
+    /// Blobs are stored as `{cache_dir}/blobs/{algorithm}/{hex}` and manifests
+    /// as `{cache_dir}/manifests/{registry_host}/{name}/{reference}`. Cached
+    /// blobs are verified against their digest when they are read, as are
+    /// manifests referenced by digest.
+    ///
+    /// Note that manifests referenced by tag are cached as well, so updates to
+    /// a tag are not picked up until the cache is cleared.
+    pub fn with_disk_cache(mut self, cache_dir: PathBuf) -> Self {
+        self.cache = Some(DiskCache::new(cache_dir));
+        self
+    }
+
+    /// Remove all content from the disk cache.
+    pub fn clear_cache(&self) -> Result<(), std::io::Error> {
+        match self.cache {
+            Some(ref cache) => cache.clear(),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the combined size of all content in the disk cache in bytes.
+    pub fn cache_size_bytes(&self) -> Result<u64, std::io::Error> {
+        match self.cache {
+            Some(ref cache) => cache.size_bytes(),
+            None => Ok(0),
         }
     }
 
+    /// Use a credential store to look up credentials for this registry.
+    ///
+    /// The credentials are presented to the token server when answering an
+    /// authentication challenge.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    /// use opencontainers::distribution::EnvCredentialStore;
+    /// use std::sync::Arc;
+    ///
+    /// let registry = Registry::new("https://registry-1.docker.io")
+    ///     .with_credential_store(Arc::new(EnvCredentialStore::default()));
+    /// ```
+    pub fn with_credential_store(mut self, store: Arc<dyn CredentialStore>) -> Self {
+        self.credential_store = Some(store);
+        self
+    }
+
+    /// Authenticate all requests to this registry.
+    ///
+    /// The `Authorization` header is sent with every request, unless a token
+    /// has been obtained by answering an authentication challenge. Basic
+    /// credentials are also presented to the token server if no credential
+    /// store is configured.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    /// use opencontainers::distribution::RegistryAuth;
+    ///
+    /// let registry = Registry::new("https://registry.example.com").with_auth(RegistryAuth::Basic {
+    ///     username: "user".into(),
+    ///     password: "pass".into(),
+    /// });
+    /// ```
+    pub fn with_auth(mut self, auth: RegistryAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Fall back to mirrors when the registry cannot be reached.
+    ///
+    /// If fetching a manifest or blob fails with a network error, the request
+    /// is retried against each mirror in order. Like the registry URL, mirror
+    /// URLs should **not** contain a trailing slash.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    /// let registry = Registry::new("https://registry-1.docker.io")
+    ///     .with_mirrors(vec!["https://mirror.gcr.io".into()]);
+    /// ```
+    pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Return the credentials for this registry from the credential store, or
+    /// from the configured [RegistryAuth].
+    fn credentials(&self) -> Option<Credentials> {
+        self.credential_store
+            .as_ref()
+            .and_then(|store| store.get_credentials(&self.url))
+            .or_else(|| match self.auth {
+                RegistryAuth::Basic {
+                    ref username,
+                    ref password,
+                } => Some(Credentials::new(username, password)),
+                RegistryAuth::DockerTokenService(ref credentials) => credentials.clone(),
+                _ => None,
+            })
+    }
+
     fn try_auth(
         &self,
         authenticate: &reqwest::header::HeaderValue,
     ) -> Result<Vec<Credential>, RegistryError> {
-        auth::do_challenge(&self.client, authenticate)
+        auth::do_challenge(&self.client, authenticate, self.credentials().as_ref())
     }
 
     fn attempt_request(
         &self,
+        method: Method,
         url: &str,
         headers: Option<&reqwest::header::HeaderMap>,
+        body: Option<&[u8]>,
         cred: Option<&Credential>,
     ) -> Result<Result<reqwest::Response, reqwest::Response>, RegistryError> {
-        let mut request = self.client.get(url);
+        let mut request = self.client.request(method, url);
 
         if let Some(headers) = headers {
             request = request.headers(headers.clone());
         }
 
+        if let Some(body) = body {
+            request = request.body(body.to_vec());
+        }
+
         if let Some(credential) = cred {
             request = request.authenticate(&credential);
+        } else if self.auth != RegistryAuth::Anonymous {
+            request = request.authenticate_with(&self.auth);
         } else {
             info!("Attempting unauthenticated request");
         }
@@ -114,12 +521,86 @@ impl Registry {
         info!("got response: {:?}", response);
 
         if status.is_success() {
+            if self.served_by_registry(response.url().as_str()) {
+                Self::check_api_version(&response)?;
+            }
+
             return Ok(Ok(response));
         }
 
         Ok(Err(response))
     }
 
+    /// Return whether a response from `url` was served by the registry or one
+    /// of its mirrors.
+    ///
+    /// Blobs are commonly served through redirects to storage backends that
+    /// don't speak the registry API, so only responses served by the registry
+    /// itself are checked for the API version.
+    fn served_by_registry(&self, url: &str) -> bool {
+        let origin = match reqwest::Url::parse(url) {
+            Ok(url) => url.origin(),
+            Err(_) => return false,
+        };
+
+        std::iter::once(&self.url)
+            .chain(self.mirrors.iter())
+            .filter_map(|base| reqwest::Url::parse(base).ok())
+            .any(|base| base.origin() == origin)
+    }
+
+    /// Check that a response was served by a registry implementing the V2 API.
+    ///
+    /// Such registries send a `Docker-Distribution-API-Version: registry/2.0`
+    /// header with their responses.
+    pub fn check_api_version(response: &reqwest::Response) -> Result<(), RegistryError> {
+        Self::check_api_version_header(
+            response
+                .headers()
+                .get("Docker-Distribution-API-Version")
+                .map(|version| version.as_bytes()),
+        )
+    }
+
+    /// Check the value of a `Docker-Distribution-API-Version` header, if
+    /// present.
+    fn check_api_version_header(version: Option<&[u8]>) -> Result<(), RegistryError> {
+        let version = version.ok_or(RegistryError::MissingApiVersionHeader)?;
+
+        match std::str::from_utf8(version) {
+            Ok(API_VERSION) => Ok(()),
+            Ok(other) => Err(RegistryError::ApiVersionMismatch { got: other.into() }),
+            Err(_) => Err(RegistryError::ApiVersionMismatch {
+                got: format!("{:?}", version),
+            }),
+        }
+    }
+
+    /// Check that the registry is reachable and implements the V2 API.
+    ///
+    /// Both a successful response and an authentication challenge to `GET
+    /// /v2/` indicate a live registry, so credentials are not checked. Use
+    /// [Registry::check_auth] for that.
+    pub fn check_connectivity(&self) -> Result<(), RegistryError> {
+        let url = format!("{}/v2/", self.url);
+
+        match self.attempt_request(Method::GET, &url, None, None, None)? {
+            Ok(_) => Ok(()),
+            Err(ref response) if response.status() == StatusCode::UNAUTHORIZED => Ok(()),
+            Err(response) => Err(RegistryError::from_response(response)),
+        }
+    }
+
+    /// Check that the configured credentials are accepted by the registry.
+    ///
+    /// If the registry presents an authentication challenge to `GET /v2/`, a
+    /// token is requested for it and the request is retried with the token.
+    pub fn check_auth(&self) -> Result<(), RegistryError> {
+        let url = format!("{}/v2/", self.url);
+
+        self.get(&url, None).map(|_| ())
+    }
+
     /// Perform a GET request on the Registry, handling authentication.
     ///
     /// # Authentication
@@ -142,27 +623,67 @@ impl Registry {
         url: &str,
         headers: Option<&reqwest::header::HeaderMap>,
     ) -> Result<reqwest::Response, RegistryError> {
+        match self.send(Method::GET, url, headers, None)? {
+            Ok(response) => Ok(response),
+            Err(response) => Err(RegistryError::from_response(response)),
+        }
+    }
+
+    /// Perform a GET request for `path` on the Registry, retrying on the
+    /// configured mirrors if the registry cannot be reached.
+    fn get_with_mirrors(
+        &self,
+        path: &str,
+        headers: Option<&reqwest::header::HeaderMap>,
+    ) -> Result<reqwest::Response, RegistryError> {
+        let error = match self.get(&format!("{}{}", self.url, path), headers) {
+            Err(RegistryError::ReqwestError(e)) => e,
+            other => return other,
+        };
+
+        warn!("Could not reach {}: {}", self.url, error);
+
+        let mut last_error = RegistryError::ReqwestError(error);
+        for mirror in &self.mirrors {
+            match self.get(&format!("{}{}", mirror, path), headers) {
+                Ok(response) => {
+                    info!("Fetched {} from mirror {}", path, mirror);
+                    return Ok(response);
+                }
+                Err(RegistryError::ReqwestError(e)) => {
+                    warn!("Could not reach mirror {}: {}", mirror, e);
+                    last_error = RegistryError::ReqwestError(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Send a request to the Registry, handling authentication like `get`.
+    ///
+    /// Returns the response as an error if its status does not indicate
+    /// success and is not an authentication challenge.
+    fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: Option<&reqwest::header::HeaderMap>,
+        body: Option<&[u8]>,
+    ) -> Result<Result<reqwest::Response, reqwest::Response>, RegistryError> {
         // Try to use the credential if it is cached
         let credential = self.credential_cache.get(url);
 
         // Attempt request
-        let response = match self.attempt_request(url, headers, credential)? {
-            Ok(response) => return Ok(response),
+        let response = match self.attempt_request(method.clone(), url, headers, body, credential)? {
+            Ok(response) => return Ok(Ok(response)),
             Err(response) => response,
         };
 
         // Unauthorized
-        let unauthorized = response.status() == StatusCode::UNAUTHORIZED;
-        let has_authenticate = response
-            .headers()
-            .contains_key(reqwest::header::WWW_AUTHENTICATE);
-
-        if unauthorized && !has_authenticate {
-            return Err(RegistryError::InvalidAuthenticationChallenge(
-                "No authentication challenge presented".into(),
-            ));
-        } else if !unauthorized {
-            return Err(RegistryError::CouldNotGetToken(response.status()));
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(Err(response));
         }
 
         info!("Authentication required");
@@ -171,44 +692,2045 @@ impl Registry {
             .headers()
             .get(reqwest::header::WWW_AUTHENTICATE)
             .ok_or(RegistryError::InvalidAuthenticationChallenge(
-                "Missing WWW-Authenticate Header".into(),
+                "No authentication challenge presented".into(),
             ))?;
 
         let credentials = self.try_auth(authenticate)?;
 
         // Attempt with each credential we got
         for credential in credentials {
-            if let Ok(response) = self.attempt_request(url, headers, Some(&credential))? {
+            if let Ok(response) =
+                self.attempt_request(method.clone(), url, headers, body, Some(&credential))?
+            {
                 info!("Got response: {:?}", response);
 
                 // TODO: Cache credential.
-                return Ok(response);
+                return Ok(Ok(response));
             }
         }
 
         Err(RegistryError::CouldNotAuthenticate)
     }
 
-    /// Create an image handle for a given image
+    /// Fetch a manifest along with its media type, digest and size.
     ///
-    /// The type parameter has a trait bound on [image::ImageSelector], which can
-    /// be implemented to select which image to use when pulling from a
-    /// fat manifest.
-    /// For most cases the [image::ImagePlatformSelector] should do just fine.
+    /// `reference` may either be a tag or a digest.
     ///
     /// # Example
     /// ```
     ///# extern crate opencontainers;
     ///# use opencontainers::Registry;
-    ///# use opencontainers::image::TestImageSelector as ImagePlatformSelector;
     ///# let registry = Registry::new("https://registry-1.docker.io");
-    /// let manifest = registry.image::<ImagePlatformSelector>("library/hello-world", "latest")
-    ///     .expect("Could not get image");
+    /// let response = registry.get_manifest_with_content_type("library/hello-world", "latest")
+    ///     .expect("Could not get manifest");
+    /// println!("{} ({}, {} bytes)", response.digest, response.content_type, response.size);
     /// ```
-    pub fn image<IS>(&self, name: &str, reference: &str) -> Result<Image, RegistryError>
-    where
-        IS: crate::image::ImageSelector,
-    {
-        Image::new::<IS>(self, name, reference)
+    pub fn get_manifest_with_content_type(
+        &self,
+        image_name: &str,
+        reference: &str,
+    ) -> Result<ManifestResponse, RegistryError> {
+        if let Some(ref cache) = self.cache {
+            if let Some((content_type, body)) = cache.get_manifest(&self.url, image_name, reference)
+            {
+                let digest = Digest::compute(DigestAlgorithm::Sha256, &body);
+                let size = body.len();
+                return Self::parse_manifest_response(body, content_type, digest, size);
+            }
+        }
+
+        let path = format!("/v2/{}/manifests/{}", image_name, reference);
+
+        let headers = self.manifest_accept_headers()?;
+        let mut response = self.get_with_mirrors(&path, Some(&headers))?;
+
+        #[allow(clippy::or_fun_call)]
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .ok_or(RegistryError::InvalidResponse(
+                "Missing Content-Type header".into(),
+            ))?
+            .to_str()
+            .map_err(|e| RegistryError::InvalidResponse(format!("Invalid Content-Type: {}", e)))?
+            .to_owned();
+
+        let header_digest = match response.headers().get("Docker-Content-Digest") {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .map_err(|e| {
+                        RegistryError::InvalidResponse(format!(
+                            "Invalid Docker-Content-Digest: {}",
+                            e
+                        ))
+                    })?
+                    .parse::<Digest>()
+                    .map_err(RegistryError::ManifestError)?,
+            ),
+            None => None,
+        };
+
+        let content_length = response.content_length();
+
+        let mut body: Vec<u8> = vec![];
+        response
+            .copy_to(&mut body)
+            .map_err(RegistryError::ReqwestError)?;
+
+        let digest = Self::check_manifest_digest(&body, header_digest)?;
+        let size = content_length.map_or(body.len(), |length| length as usize);
+
+        if let Some(ref cache) = self.cache {
+            cache.put_manifest(&self.url, image_name, reference, &content_type, &body)?;
+        }
+
+        Self::parse_manifest_response(body, content_type, digest, size)
+    }
+
+    /// Return headers accepting the manifest media types of the registry, see
+    /// [Registry::with_media_type_preference].
+    pub(crate) fn manifest_accept_headers(
+        &self,
+    ) -> Result<reqwest::header::HeaderMap, RegistryError> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        let accept = self.manifest_media_types.join(",");
+        headers.insert(
+            reqwest::header::ACCEPT,
+            accept.parse().map_err(|_| {
+                RegistryError::ManifestError(ManifestError::InvalidMediaType(accept.clone()))
+            })?,
+        );
+
+        Ok(headers)
+    }
+
+    /// Check a manifest against the digest the registry sent for it, if any.
+    ///
+    /// Returns the digest of the manifest, which is its SHA-256 digest if the
+    /// registry did not send one.
+    fn check_manifest_digest(
+        body: &[u8],
+        header_digest: Option<Digest>,
+    ) -> Result<Digest, RegistryError> {
+        // Make sure the manifest was not tampered with on the way.
+        if let Some(ref expected) = header_digest {
+            DigestMismatchError::check(expected, body, "manifest")?;
+        }
+
+        Ok(header_digest.unwrap_or_else(|| Digest::compute(DigestAlgorithm::Sha256, body)))
+    }
+
+    fn parse_manifest_response(
+        body: Vec<u8>,
+        content_type: String,
+        digest: Digest,
+        size: usize,
+    ) -> Result<ManifestResponse, RegistryError> {
+        let manifest = std::str::from_utf8(&body)
+            .map_err(|e| RegistryError::InvalidResponse(format!("Invalid manifest: {}", e)))?
+            .parse()
+            .map_err(RegistryError::ManifestError)?;
+
+        Ok(ManifestResponse {
+            manifest,
+            content_type,
+            digest,
+            size,
+        })
+    }
+
+    /// Check that a blob exists without downloading it.
+    ///
+    /// If the blob does not exist, [RegistryError::HttpStatus] with status
+    /// 404 is returned.
+    pub fn head_blob(&self, name: &str, digest: &Digest) -> Result<BlobInfo, RegistryError> {
+        let url = format!("{}/v2/{}/blobs/{}", self.url, name, digest);
+
+        let response = match self.send(Method::HEAD, &url, None, None)? {
+            Ok(response) => response,
+            Err(response) => return Err(RegistryError::from_response(response)),
+        };
+
+        // The body of a HEAD response is empty, so the size is only known
+        // from the header.
+        #[allow(clippy::or_fun_call)]
+        let size = response
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse().ok())
+            .ok_or(RegistryError::InvalidResponse(
+                "Missing or invalid Content-Length header".into(),
+            ))?;
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_owned();
+
+        Ok(BlobInfo { size, content_type })
+    }
+
+    /// Return true if a blob exists in a repository.
+    pub fn blob_exists(&self, name: &str, digest: &Digest) -> Result<bool, RegistryError> {
+        match self.head_blob(name, digest) {
+            Ok(_) => Ok(true),
+            Err(RegistryError::HttpStatus { status: 404, .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fetch a blob from a repository by its digest.
+    ///
+    /// Redirects, e.g. to a storage backend, are followed. The blob is
+    /// returned as stored in the registry and is not decompressed.
+    ///
+    /// # Example
+    /// ```no_run
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    /// use std::io::Read;
+    ///
+    /// let registry = Registry::new("https://registry-1.docker.io");
+    /// let digest = "sha256:fce289e99eb9bca977dae136fbe2a82b6b7d4c372474c9235adc1741675f587e"
+    ///     .parse()
+    ///     .unwrap();
+    /// let mut config = String::new();
+    /// registry
+    ///     .get_blob("library/hello-world", &digest)
+    ///     .expect("Could not get blob")
+    ///     .read_to_string(&mut config)
+    ///     .expect("Could not read blob");
+    /// ```
+    pub fn get_blob(&self, name: &str, digest: &Digest) -> Result<impl Read, RegistryError> {
+        self.fetch_blob(name, digest)
+    }
+
+    /// Fetch a blob, using the disk cache if configured.
+    ///
+    /// When caching, the blob is downloaded into the cache and verified
+    /// against its digest before it is returned.
+    pub(crate) fn fetch_blob(
+        &self,
+        name: &str,
+        digest: &Digest,
+    ) -> Result<Box<dyn Read>, RegistryError> {
+        let path = format!("/v2/{}/blobs/{}", name, digest);
+
+        let cache = match self.cache {
+            Some(ref cache) => cache,
+            None => return Ok(Box::new(self.get_with_mirrors(&path, None)?)),
+        };
+
+        if let Some(file) = cache.get_blob(digest)? {
+            return Ok(Box::new(file));
+        }
+
+        let mut response = self.get_with_mirrors(&path, None)?;
+        Ok(Box::new(cache.put_blob(digest, &mut response)?))
+    }
+
+    /// Fetch content from a URL outside of the registry API, e.g. a foreign
+    /// layer.
+    ///
+    /// No registry authentication is sent with the request. As the content is
+    /// not served by the registry, it is verified against `digest`: reading
+    /// fails with [std::io::ErrorKind::InvalidData] once the end of content
+    /// is reached if it does not match.
+    pub(crate) fn fetch_url(
+        &self,
+        url: &str,
+        digest: &Digest,
+    ) -> Result<Box<dyn Read>, RegistryError> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .map_err(RegistryError::ReqwestError)?;
+
+        if !response.status().is_success() {
+            return Err(RegistryError::from_response(response));
+        }
+
+        Ok(Box::new(CheckedReader {
+            inner: Some(digest.verify_reader(response)?),
+        }))
+    }
+
+    /// Return the URL of the next page from the `Link` header of a paginated
+    /// response, if any.
+    fn next_page_url(&self, response: &reqwest::Response) -> Option<String> {
+        let link = response
+            .headers()
+            .get(reqwest::header::LINK)?
+            .to_str()
+            .ok()?;
+
+        let next = link.split(',').find(|part| part.contains("rel=\"next\""))?;
+        let start = next.find('<')? + 1;
+        let end = next.find('>')?;
+        let target = next.get(start..end)?;
+
+        if target.starts_with("http://") || target.starts_with("https://") {
+            Some(target.into())
+        } else {
+            Some(format!("{}{}", self.url, target))
+        }
+    }
+
+    /// Fetch all pages of a paginated endpoint, following `Link` headers.
+    fn get_all_pages<T>(&self, url: String) -> Result<Vec<T>, RegistryError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut pages = vec![];
+        let mut next = Some(url);
+
+        while let Some(url) = next {
+            let mut response = self.get(&url, None)?;
+            next = self.next_page_url(&response);
+            pages.push(response.json().map_err(RegistryError::ReqwestError)?);
+        }
+
+        Ok(pages)
+    }
+
+    /// List all tags of a repository.
+    ///
+    /// All pages of the tag list are fetched, following the `Link` header.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let tags = registry.list_tags("library/hello-world")
+    ///     .expect("Could not list tags");
+    /// assert!(tags.contains(&"latest".to_string()));
+    /// ```
+    pub fn list_tags(&self, image_name: &str) -> Result<Vec<String>, RegistryError> {
+        let url = format!("{}/v2/{}/tags/list", self.url, image_name);
+
+        let mut tags = vec![];
+        for page in self.get_all_pages::<serde_json::Value>(url)? {
+            let page: TagList =
+                serde_json::from_value(page).map_err(RegistryError::TagListError)?;
+            tags.extend(page.tags.unwrap_or_default());
+        }
+
+        Ok(tags)
+    }
+
+    /// List all repositories on the registry.
+    ///
+    /// `limit` is sent as the `n` parameter and bounds the size of each page,
+    /// not the total number of repositories returned. All pages are fetched,
+    /// following the `Link` header.
+    ///
+    /// Note that many public registries, including Docker Hub, do not serve
+    /// the catalog endpoint.
+    pub fn list_repositories(&self, limit: Option<u32>) -> Result<Vec<String>, RegistryError> {
+        let url = match limit {
+            Some(n) => format!("{}/v2/_catalog?n={}", self.url, n),
+            None => format!("{}/v2/_catalog", self.url),
+        };
+
+        Ok(self
+            .get_all_pages::<Catalog>(url)?
+            .into_iter()
+            .flat_map(|page| page.repositories.unwrap_or_default())
+            .collect())
+    }
+
+    /// List the tags of a repository matching a glob-style pattern.
+    ///
+    /// In `pattern`, `*` matches any sequence of characters and `?` matches
+    /// any single character. All pages of the tag list are fetched and
+    /// filtered client-side.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let tags = registry.list_tags_matching("library/hello-world", "lat*")
+    ///     .expect("Could not list tags");
+    /// assert_eq!(tags, vec!["latest"]);
+    /// ```
+    pub fn list_tags_matching(
+        &self,
+        image_name: &str,
+        pattern: &str,
+    ) -> Result<Vec<String>, RegistryError> {
+        Ok(self
+            .list_tags(image_name)?
+            .into_iter()
+            .filter(|tag| glob_matches(pattern, tag))
+            .collect())
+    }
+
+    /// Start uploading a blob to a repository.
+    ///
+    /// The returned session implements the chunked upload of the
+    /// [distribution
+    /// spec](https://github.com/opencontainers/distribution-spec/blob/master/spec.md#pushing-a-blob-in-chunks).
+    ///
+    /// # Example
+    /// ```no_run
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    /// use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    ///
+    ///# let registry = Registry::new("https://registry.example.com");
+    /// let data = b"hello world";
+    /// let mut upload = registry.start_upload("test/hello").expect("Could not start upload");
+    /// upload.upload_chunk(data).expect("Could not upload chunk");
+    /// upload
+    ///     .finish(&Digest::compute(DigestAlgorithm::Sha256, data))
+    ///     .expect("Could not finish upload");
+    /// ```
+    pub fn start_upload(&self, name: &str) -> Result<UploadSession, RegistryError> {
+        UploadSession::start(self, name)
+    }
+
+    /// Upload a manifest to a repository, tagging it with `reference`.
+    ///
+    /// All blobs referenced by the manifest must have been uploaded before.
+    pub fn push_manifest(
+        &self,
+        name: &str,
+        reference: &str,
+        manifest: &ManifestV2_2,
+    ) -> Result<(), RegistryError> {
+        let url = format!("{}/v2/{}/manifests/{}", self.url, name, reference);
+        let body = serde_json::to_vec(manifest)
+            .map_err(|e| RegistryError::ManifestError(ManifestError::JsonError(e)))?;
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            manifest.media_type.parse().map_err(|_| {
+                RegistryError::ManifestError(ManifestError::InvalidMediaType(
+                    manifest.media_type.clone(),
+                ))
+            })?,
+        );
+
+        upload::expect_status(
+            self.send(Method::PUT, &url, Some(&headers), Some(&body))?,
+            StatusCode::CREATED,
+        )?;
+
+        Ok(())
+    }
+
+    /// Create an image handle for a given image
+    ///
+    /// The type parameter has a trait bound on [image::ImageSelector], which can
+    /// be implemented to select which image to use when pulling from a
+    /// fat manifest.
+    /// For most cases the [image::ImagePlatformSelector] should do just fine.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# use opencontainers::image::TestImageSelector as ImagePlatformSelector;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let manifest = registry.image::<ImagePlatformSelector>("library/hello-world", "latest")
+    ///     .expect("Could not get image");
+    /// ```
+    pub fn image<IS>(&self, name: &str, reference: &str) -> Result<Image, RegistryError>
+    where
+        IS: crate::image::ImageSelector,
+    {
+        Image::new::<IS>(self, name, reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::manifest::ManifestV2Schema;
+    use crate::test_util::temp_dir;
+    use mockito::mock;
+
+    const MANIFEST: &str = include_str!("../image/test/manifest-v2-2.test.json");
+    const MANIFEST_DIGEST: &str =
+        "sha256:5e6de772243200898c0ba7333fbb78130d6f53263d84863ff7a90ff4eab3cc0c";
+
+    #[test]
+    fn test_get_manifest_with_content_type() {
+        let _m = mock("GET", "/v2/test/content-type/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-content-digest", MANIFEST_DIGEST)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let response = registry
+            .get_manifest_with_content_type("test/content-type", "latest")
+            .expect("Could not get manifest");
+
+        assert_eq!(
+            ManifestV2Schema::from(&response.manifest),
+            ManifestV2Schema::Schema2
+        );
+        assert_eq!(
+            response.content_type,
+            "application/vnd.docker.distribution.manifest.v2+json"
+        );
+        assert_eq!(response.digest.to_string(), MANIFEST_DIGEST);
+        assert_eq!(response.size, MANIFEST.len());
+    }
+
+    #[test]
+    fn test_get_manifest_with_content_type_without_digest() {
+        let _m = mock("GET", "/v2/test/no-digest/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let response = registry
+            .get_manifest_with_content_type("test/no-digest", "latest")
+            .expect("Could not get manifest");
+
+        assert_eq!(
+            response.digest,
+            Digest::compute(DigestAlgorithm::Sha256, MANIFEST.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_get_manifest_digest_mismatch() {
+        let tampered_digest =
+            "sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f";
+
+        let _m = mock("GET", "/v2/test/tampered/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-content-digest", tampered_digest)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        match registry.get_manifest_with_content_type("test/tampered", "latest") {
+            Err(RegistryError::DigestMismatch(DigestMismatchError {
+                expected, actual, ..
+            })) => {
+                assert_eq!(expected.to_string(), tampered_digest);
+                assert_eq!(actual.to_string(), MANIFEST_DIGEST);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_http_status_error() {
+        let _m = mock("GET", "/v2/test/unknown/manifests/latest")
+            .with_status(404)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"errors": [{"code": "MANIFEST_UNKNOWN", "message": "manifest unknown", "detail": {"Tag": "latest"}}]}"#,
+            )
+            .create();
+        let _m2 = mock("GET", "/v2/test/broken/manifests/latest")
+            .with_status(500)
+            .with_body("Internal Server Error")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+
+        let url = format!("{}/v2/test/unknown/manifests/latest", registry.url);
+        match registry.get(&url, None) {
+            Err(RegistryError::HttpStatus {
+                status,
+                url: error_url,
+                errors,
+            }) => {
+                assert_eq!(status, 404);
+                assert_eq!(error_url, url);
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].code, "MANIFEST_UNKNOWN");
+                assert_eq!(errors[0].to_string(), "MANIFEST_UNKNOWN: manifest unknown");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let url = format!("{}/v2/test/broken/manifests/latest", registry.url);
+        match registry.get(&url, None) {
+            Err(RegistryError::HttpStatus { status, errors, .. }) => {
+                assert_eq!(status, 500);
+                assert!(errors.is_empty());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_api_version() {
+        let _m = mock("GET", "/v2/test/api-version/ok")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let url = format!("{}/v2/test/api-version/ok", registry.url);
+        registry
+            .get(&url, None)
+            .expect("Could not get response with valid API version");
+    }
+
+    #[test]
+    fn test_check_api_version_mismatch() {
+        let _m = mock("GET", "/v2/test/api-version/mismatch")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/1.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let url = format!("{}/v2/test/api-version/mismatch", registry.url);
+        match registry.get(&url, None) {
+            Err(RegistryError::ApiVersionMismatch { got }) => assert_eq!(got, "registry/1.0"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_api_version_missing() {
+        let _m = mock("GET", "/v2/test/api-version/missing")
+            .with_status(200)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let url = format!("{}/v2/test/api-version/missing", registry.url);
+        match registry.get(&url, None) {
+            Err(RegistryError::MissingApiVersionHeader) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_served_by_registry() {
+        let registry = Registry::new("https://reg.example.com")
+            .with_mirrors(vec!["https://mirror.example.com:5000".into()]);
+
+        assert!(registry.served_by_registry("https://reg.example.com/v2/"));
+        assert!(registry.served_by_registry("https://mirror.example.com:5000/v2/test/blobs/x"));
+        assert!(!registry.served_by_registry("https://reg.example.com.evil/v2/"));
+        assert!(!registry.served_by_registry("https://reg.example.com:8443/v2/"));
+        assert!(!registry.served_by_registry("http://reg.example.com/v2/"));
+        assert!(!registry.served_by_registry("https://mirror.example.com/v2/"));
+        assert!(!registry.served_by_registry("not a url"));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("v1.*", "v1.0"));
+        assert!(glob_matches("v1.*", "v1."));
+        assert!(!glob_matches("v1.*", "v2.0"));
+        assert!(glob_matches("*-rc", "v1.2-rc"));
+        assert!(!glob_matches("*-rc", "v1.2-rc1"));
+        assert!(glob_matches("v?.?", "v1.2"));
+        assert!(!glob_matches("v?.?", "v1.23"));
+        assert!(glob_matches("*a*b*", "xxaxxbxx"));
+        assert!(!glob_matches("*a*b*", "xxbxxaxx"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("latest", "latest"));
+        assert!(!glob_matches("latest", "latest2"));
+    }
+
+    #[test]
+    fn test_list_tags_matching() {
+        let _page1 = mock("GET", "/v2/test/glob/tags/list")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_header(
+                "link",
+                r#"</v2/test/glob/tags/list?n=3&last=latest>; rel="next""#,
+            )
+            .with_body(r#"{"name": "test/glob", "tags": ["v1.0", "v1.1", "latest"]}"#)
+            .create();
+        let _page2 = mock("GET", "/v2/test/glob/tags/list?n=3&last=latest")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"name": "test/glob", "tags": ["v2.0", "v1.2-rc"]}"#)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let tags = registry
+            .list_tags_matching("test/glob", "v1.*")
+            .expect("Could not list tags");
+
+        assert_eq!(tags, vec!["v1.0", "v1.1", "v1.2-rc"]);
+    }
+
+    #[test]
+    fn test_list_tags() {
+        let _page1 = mock("GET", "/v2/test/tags/tags/list")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_header(
+                "link",
+                r#"</v2/test/tags/tags/list?n=2&last=v1.1>; rel="next""#,
+            )
+            .with_body(r#"{"name": "test/tags", "tags": ["v1.0", "v1.1"]}"#)
+            .create();
+        let _page2 = mock("GET", "/v2/test/tags/tags/list?n=2&last=v1.1")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"name": "test/tags", "tags": ["latest"]}"#)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let tags = registry
+            .list_tags("test/tags")
+            .expect("Could not list tags");
+
+        assert_eq!(tags, vec!["v1.0", "v1.1", "latest"]);
+    }
+
+    #[test]
+    fn test_list_tags_empty() {
+        let _m = mock("GET", "/v2/test/no-tags/tags/list")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"name": "test/no-tags", "tags": null}"#)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let tags = registry
+            .list_tags("test/no-tags")
+            .expect("Could not list tags");
+
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn test_list_tags_invalid() {
+        let _m = mock("GET", "/v2/test/bad-tags/tags/list")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"name": "test/bad-tags", "tags": "latest"}"#)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        match registry.list_tags("test/bad-tags") {
+            Err(RegistryError::TagListError(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_repositories() {
+        let _page1 = mock("GET", "/v2/_catalog?n=2")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_header("link", r#"</v2/_catalog?n=2&last=test%2Fb>; rel="next""#)
+            .with_body(r#"{"repositories": ["test/a", "test/b"]}"#)
+            .create();
+        let _page2 = mock("GET", "/v2/_catalog?n=2&last=test%2Fb")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"repositories": ["test/c"]}"#)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let repositories = registry
+            .list_repositories(Some(2))
+            .expect("Could not list repositories");
+
+        assert_eq!(repositories, vec!["test/a", "test/b", "test/c"]);
+    }
+
+    #[test]
+    fn test_chunked_upload() {
+        use mockito::Matcher;
+
+        let data = b"hello world";
+        let digest = Digest::compute(DigestAlgorithm::Sha256, data);
+
+        let start = mock("POST", "/v2/test/upload/blobs/uploads/")
+            .with_status(202)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("location", "/v2/test/upload/blobs/uploads/1")
+            .create();
+        let chunk1 = mock("PATCH", "/v2/test/upload/blobs/uploads/1")
+            .match_header("content-range", "0-4")
+            .match_header("content-type", "application/octet-stream")
+            .match_body("hello")
+            .with_status(202)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("location", "/v2/test/upload/blobs/uploads/2")
+            .create();
+        let chunk2 = mock("PATCH", "/v2/test/upload/blobs/uploads/2")
+            .match_header("content-range", "5-10")
+            .match_body(" world")
+            .with_status(202)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header(
+                "location",
+                &format!("{}/v2/test/upload/blobs/uploads/3", mockito::server_url()),
+            )
+            .create();
+        let finish = mock("PUT", "/v2/test/upload/blobs/uploads/3")
+            .match_query(Matcher::UrlEncoded("digest".into(), digest.to_string()))
+            .with_status(201)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let mut upload = registry
+            .start_upload("test/upload")
+            .expect("Could not start upload");
+        upload
+            .upload_chunk(&data[..5])
+            .expect("Could not upload chunk");
+        upload
+            .upload_chunk(&data[5..])
+            .expect("Could not upload chunk");
+        assert_eq!(upload.offset(), data.len());
+        upload.finish(&digest).expect("Could not finish upload");
+
+        start.assert();
+        chunk1.assert();
+        chunk2.assert();
+        finish.assert();
+    }
+
+    #[test]
+    fn test_upload_reader() {
+        use mockito::Matcher;
+
+        /// Returns a single byte per read, like a slow network stream.
+        struct ByteReader<'a>(&'a [u8]);
+
+        impl Read for ByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.is_empty() || buf.is_empty() {
+                    return Ok(0);
+                }
+                buf[0] = self.0[0];
+                self.0 = &self.0[1..];
+                Ok(1)
+            }
+        }
+
+        let data = b"hello world";
+        let digest = Digest::compute(DigestAlgorithm::Sha256, data);
+
+        let start = mock("POST", "/v2/test/upload-reader/blobs/uploads/")
+            .with_status(202)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("location", "/v2/test/upload-reader/blobs/uploads/1")
+            .create();
+        let chunks = ["hello", " worl", "d"]
+            .iter()
+            .enumerate()
+            .map(|(i, body)| {
+                mock(
+                    "PATCH",
+                    format!("/v2/test/upload-reader/blobs/uploads/{}", i + 1).as_str(),
+                )
+                .match_body(*body)
+                .with_status(202)
+                .with_header("docker-distribution-api-version", "registry/2.0")
+                .with_header(
+                    "location",
+                    &format!("/v2/test/upload-reader/blobs/uploads/{}", i + 2),
+                )
+                .expect(1)
+                .create()
+            })
+            .collect::<Vec<_>>();
+        let finish = mock("PUT", "/v2/test/upload-reader/blobs/uploads/4")
+            .match_query(Matcher::UrlEncoded("digest".into(), digest.to_string()))
+            .with_status(201)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let mut upload = registry
+            .start_upload("test/upload-reader")
+            .expect("Could not start upload");
+        upload
+            .upload_reader(ByteReader(data), &mut Vec::new(), 5)
+            .expect("Could not upload blob");
+        assert_eq!(upload.offset(), data.len());
+        upload.finish(&digest).expect("Could not finish upload");
+
+        start.assert();
+        for m in &chunks {
+            m.assert();
+        }
+        finish.assert();
+    }
+
+    #[test]
+    fn test_upload_failed() {
+        let _m = mock("POST", "/v2/test/upload-denied/blobs/uploads/")
+            .with_status(403)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        match registry.start_upload("test/upload-denied") {
+            Err(RegistryError::UploadFailed(status)) => assert_eq!(status, StatusCode::FORBIDDEN),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_push_manifest() {
+        let manifest: ManifestV2_2 = serde_json::from_str(MANIFEST).unwrap();
+
+        let m = mock("PUT", "/v2/test/push/manifests/latest")
+            .match_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_status(201)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        registry
+            .push_manifest("test/push", "latest", &manifest)
+            .expect("Could not push manifest");
+        m.assert();
+    }
+
+    #[test]
+    fn test_image_builder() {
+        use crate::image::{ImageError, TestImageSelector};
+
+        let m = mock("GET", "/v2/test/builder/manifests/v1")
+            .match_header("authorization", "Basic dXNlcjpwYXNz")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+
+        let image = Image::builder()
+            .registry(&mockito::server_url())
+            .name("test/builder")
+            .tag("v1")
+            .auth(RegistryAuth::Basic {
+                username: "user".into(),
+                password: "pass".into(),
+            })
+            .build_with_selector::<TestImageSelector>()
+            .expect("Could not build image");
+        m.assert();
+
+        assert_eq!(image.name(), "test/builder");
+        assert_eq!(image.tag(), Some("v1"));
+        assert_eq!(image.registry_url(), mockito::server_url());
+
+        match Image::builder().registry(&mockito::server_url()).build() {
+            Err(ImageError::MissingField(field)) => assert_eq!(field, "name"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match Image::builder().name("test/builder").build() {
+            Err(ImageError::MissingField(field)) => assert_eq!(field, "registry"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_head_blob() {
+        let digest = Digest::compute(DigestAlgorithm::Sha256, b"hello world");
+        let path = format!("/v2/test/head/blobs/{}", digest);
+
+        let head = mock("HEAD", path.as_str())
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/octet-stream")
+            .with_header("content-length", "11")
+            .expect(2)
+            .create();
+        let get = mock("GET", path.as_str()).expect(0).create();
+
+        let missing = Digest::compute(DigestAlgorithm::Sha256, b"missing");
+        let _missing = mock("HEAD", format!("/v2/test/head/blobs/{}", missing).as_str())
+            .with_status(404)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let info = registry
+            .head_blob("test/head", &digest)
+            .expect("Could not get blob info");
+        assert_eq!(
+            info,
+            BlobInfo {
+                size: 11,
+                content_type: "application/octet-stream".into(),
+            }
+        );
+
+        assert!(registry.blob_exists("test/head", &digest).unwrap());
+        assert!(!registry.blob_exists("test/head", &missing).unwrap());
+
+        head.assert();
+        get.assert();
+    }
+
+    #[test]
+    fn test_image_copy_to() {
+        use crate::image::manifest::{ConfigV2_2, Layer, LayerMediaType, LayerV2_2};
+        use crate::image::TestImageSelector;
+        use mockito::Matcher;
+
+        let config = ConfigV2_2::for_docker_json_blob(b"{}");
+        let layer = LayerV2_2::for_blob(LayerMediaType::TarGz, b"hello").unwrap();
+        let config_digest = config.digest().clone();
+        let layer_digest = layer.digest().clone();
+        let manifest = ManifestV2_2::for_docker(config, vec![layer]);
+        let manifest = serde_json::to_string(&manifest).unwrap();
+
+        let mut mocks = vec![];
+        for name in &["src", "dst"] {
+            mocks.push(
+                mock(
+                    "GET",
+                    format!("/v2/test/copy-{}/manifests/latest", name).as_str(),
+                )
+                .with_status(200)
+                .with_header(
+                    "content-type",
+                    "application/vnd.docker.distribution.manifest.v2+json",
+                )
+                .with_header("docker-distribution-api-version", "registry/2.0")
+                .with_body(&manifest)
+                .create(),
+            );
+        }
+
+        // The config already exists at the destination, the layer does not.
+        let config_head = mock(
+            "HEAD",
+            format!("/v2/test/copy-dst/blobs/{}", config_digest).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_header("content-length", "2")
+        .create();
+        let config_get = mock(
+            "GET",
+            format!("/v2/test/copy-src/blobs/{}", config_digest).as_str(),
+        )
+        .expect(0)
+        .create();
+        let layer_head = mock(
+            "HEAD",
+            format!("/v2/test/copy-dst/blobs/{}", layer_digest).as_str(),
+        )
+        .with_status(404)
+        .create();
+        let layer_get = mock(
+            "GET",
+            format!("/v2/test/copy-src/blobs/{}", layer_digest).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body("hello")
+        .create();
+
+        let start = mock("POST", "/v2/test/copy-dst/blobs/uploads/")
+            .with_status(202)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("location", "/v2/test/copy-dst/blobs/uploads/1")
+            .create();
+        let chunk = mock("PATCH", "/v2/test/copy-dst/blobs/uploads/1")
+            .match_body("hello")
+            .with_status(202)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("location", "/v2/test/copy-dst/blobs/uploads/2")
+            .create();
+        let finish = mock("PUT", "/v2/test/copy-dst/blobs/uploads/2")
+            .match_query(Matcher::UrlEncoded(
+                "digest".into(),
+                layer_digest.to_string(),
+            ))
+            .with_status(201)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+        let push = mock("PUT", "/v2/test/copy-dst/manifests/latest")
+            .with_status(201)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let src = registry
+            .image::<TestImageSelector>("test/copy-src", "latest")
+            .expect("Could not get source image");
+        let dst = registry
+            .image::<TestImageSelector>("test/copy-dst", "latest")
+            .expect("Could not get destination image");
+
+        src.copy_to(&dst).expect("Could not copy image");
+
+        for m in &[
+            config_head,
+            config_get,
+            layer_head,
+            layer_get,
+            start,
+            chunk,
+            finish,
+            push,
+        ] {
+            m.assert();
+        }
+    }
+
+    #[test]
+    fn test_get_layer_stream() {
+        use crate::image::TestImageSelector;
+
+        let _m = mock("GET", "/v2/test/stream/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+        let _blob = mock(
+            "GET",
+            "/v2/test/stream/blobs/sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f",
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body("compressed layer")
+        .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let image = registry
+            .image::<TestImageSelector>("test/stream", "latest")
+            .expect("Could not get image");
+        let layer = image
+            .manifest()
+            .layers()
+            .expect("Could not get layers")
+            .next()
+            .expect("Manifest has no layers");
+
+        let mut content = String::new();
+        image
+            .get_layer_stream(layer)
+            .expect("Could not get layer stream")
+            .read_to_string(&mut content)
+            .expect("Could not read layer");
+        assert_eq!(content, "compressed layer");
+    }
+
+    #[test]
+    fn test_fetch_config() {
+        use crate::image::manifest::ConfigV2_2;
+        use crate::image::TestImageSelector;
+
+        let blob: &[u8] = br#"{
+            "architecture": "amd64",
+            "os": "linux",
+            "config": { "User": "nobody" },
+            "rootfs": { "type": "layers", "diff_ids": [] }
+        }"#;
+        let config = ConfigV2_2::for_docker_json_blob(blob);
+        let tampered = ConfigV2_2::for_docker_json_blob(b"{}");
+
+        let _m = mock("GET", "/v2/test/config/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+        let _config = mock(
+            "GET",
+            format!("/v2/test/config/blobs/{}", config.digest()).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(blob)
+        .create();
+        let _tampered = mock(
+            "GET",
+            format!("/v2/test/config/blobs/{}", tampered.digest()).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(blob)
+        .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let image = registry
+            .image::<TestImageSelector>("test/config", "latest")
+            .expect("Could not get image");
+
+        let image_config = config.fetch_config(&image).expect("Could not fetch config");
+        assert_eq!(
+            image_config.config.and_then(|config| config.user),
+            Some("nobody".into())
+        );
+
+        match tampered.fetch_config(&image) {
+            Err(RegistryError::DigestMismatch(DigestMismatchError { expected, .. })) => {
+                assert_eq!(&expected, tampered.digest())
+            }
+            other => panic!("Expected a digest mismatch, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_mirror_fallback() {
+        let _m = mock("GET", "/v2/test/mirror/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .expect(1)
+            .create();
+
+        // Nothing listens on port 1, so connecting to the primary fails.
+        let registry = Registry::new("http://127.0.0.1:1")
+            .with_mirrors(vec!["http://127.0.0.1:1".into(), mockito::server_url()]);
+        let response = registry
+            .get_manifest_with_content_type("test/mirror", "latest")
+            .expect("Could not get manifest from mirror");
+        assert_eq!(response.digest.to_string(), MANIFEST_DIGEST);
+
+        _m.assert();
+
+        let registry = Registry::new("http://127.0.0.1:1");
+        match registry.get_manifest_with_content_type("test/mirror", "latest") {
+            Err(RegistryError::ReqwestError(_)) => {}
+            other => panic!("Expected a network error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_get_blob() {
+        let digest = Digest::compute(DigestAlgorithm::Sha256, b"blob content");
+
+        let _redirect = mock("GET", format!("/v2/test/blob/blobs/{}", digest).as_str())
+            .with_status(307)
+            .with_header("location", "/storage/blob")
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+        let _storage = mock("GET", "/storage/blob")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body("blob content")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let mut content = String::new();
+        registry
+            .get_blob("test/blob", &digest)
+            .expect("Could not get blob")
+            .read_to_string(&mut content)
+            .expect("Could not read blob");
+        assert_eq!(content, "blob content");
+    }
+
+    #[test]
+    fn test_image_pull() {
+        use crate::image::manifest::{ConfigV2_2, Layer, LayerMediaType, LayerV2_2};
+        use crate::image::TestImageSelector;
+
+        let layers = vec![
+            LayerV2_2::for_blob(LayerMediaType::TarGz, b"first layer").unwrap(),
+            LayerV2_2::for_blob(LayerMediaType::TarGz, b"second layer").unwrap(),
+        ];
+        let manifest = ManifestV2_2::new(ConfigV2_2::for_docker_json_blob(b"{}"), layers.clone());
+
+        let _m = mock("GET", "/v2/test/pull/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(serde_json::to_string(&manifest).unwrap())
+            .create();
+        let _first = mock(
+            "GET",
+            format!("/v2/test/pull/blobs/{}", layers[0].digest()).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body("first layer")
+        .create();
+        let _second = mock(
+            "GET",
+            format!("/v2/test/pull/blobs/{}", layers[1].digest()).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body("second layer")
+        .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let pulled = registry
+            .image::<TestImageSelector>("test/pull", "latest")
+            .expect("Could not get image")
+            .pull()
+            .expect("Could not pull image");
+
+        assert_eq!(pulled.manifest.media_type, manifest.media_type);
+        assert_eq!(pulled.manifest.config, manifest.config);
+        assert_eq!(pulled.manifest.layers, manifest.layers);
+        assert_eq!(
+            pulled.layers,
+            vec![
+                (layers[0].clone(), b"first layer".to_vec()),
+                (layers[1].clone(), b"second layer".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_layer_url_fallback() {
+        use crate::image::manifest::LayerV2_2;
+        use crate::image::TestImageSelector;
+
+        let mut archive = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, "hello.txt", &b"hello"[..])
+            .unwrap();
+        let blob = archive.into_inner().unwrap();
+        let digest = Digest::compute(DigestAlgorithm::Sha256, &blob);
+
+        let layer: LayerV2_2 = serde_json::from_value(serde_json::json!({
+            "mediaType": "application/vnd.oci.image.layer.v1.tar",
+            "size": blob.len(),
+            "digest": digest.to_string(),
+            "urls": [
+                format!("{}/foreign/missing", mockito::server_url()),
+                format!("{}/foreign/layer", mockito::server_url()),
+            ],
+        }))
+        .unwrap();
+        assert_eq!(layer.urls().map(<[String]>::len), Some(2));
+
+        let _m = mock("GET", "/v2/test/foreign/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+        let _registry_blob = mock("GET", format!("/v2/test/foreign/blobs/{}", digest).as_str())
+            .with_status(404)
+            .create();
+        let _missing = mock("GET", "/foreign/missing").with_status(404).create();
+        let foreign = mock("GET", "/foreign/layer")
+            .with_status(200)
+            .with_body(&blob)
+            .expect(1)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let image = registry
+            .image::<TestImageSelector>("test/foreign", "latest")
+            .expect("Could not get image");
+        let mut archive = image.get_layer(&layer).expect("Could not get layer");
+
+        let mut entries = archive.entries().expect("Could not read layer");
+        let mut entry = entries.next().expect("Layer is empty").unwrap();
+        assert_eq!(entry.path().unwrap().to_str(), Some("hello.txt"));
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "hello");
+
+        foreign.assert();
+    }
+
+    #[test]
+    fn test_fetch_url_digest_mismatch() {
+        let digest = Digest::compute(DigestAlgorithm::Sha256, b"foreign layer");
+
+        let _m = mock("GET", "/foreign/tampered")
+            .with_status(200)
+            .with_body("tampered layer")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let mut reader = registry
+            .fetch_url(
+                &format!("{}/foreign/tampered", mockito::server_url()),
+                &digest,
+            )
+            .expect("Could not fetch URL");
+
+        let error = reader
+            .read_to_end(&mut vec![])
+            .expect_err("tampered content was not detected");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_registry_with_client() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-client", "custom".parse().unwrap());
+        let client = Client::builder()
+            .default_headers(headers)
+            .build()
+            .expect("Could not build client");
+
+        let m = mock("GET", "/v2/test/client/tags/list")
+            .match_header("x-client", "custom")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"name": "test/client", "tags": ["latest"]}"#)
+            .expect(2)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_client(client);
+        assert_eq!(registry.list_tags("test/client").unwrap(), vec!["latest"]);
+        assert_eq!(registry.list_tags("test/client").unwrap(), vec!["latest"]);
+
+        m.assert();
+    }
+
+    #[test]
+    fn test_media_type_preference() {
+        let m = mock("GET", "/v2/test/accept/manifests/latest")
+            .match_header(
+                "accept",
+                "application/vnd.oci.image.manifest.v1+json,application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .expect(1)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_media_type_preference(&[
+            "application/vnd.oci.image.manifest.v1+json",
+            "application/vnd.docker.distribution.manifest.v2+json",
+        ]);
+        registry
+            .get_manifest_with_content_type("test/accept", "latest")
+            .expect("Could not get manifest");
+
+        m.assert();
+    }
+
+    #[test]
+    fn test_media_type_preference_empty() {
+        let m = mock("GET", "/v2/test/accept-default/manifests/latest")
+            .match_header("accept", MANIFEST_ACCEPT_TYPES.join(",").as_str())
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .expect(1)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_media_type_preference(&[]);
+        registry
+            .get_manifest_with_content_type("test/accept-default", "latest")
+            .expect("Could not get manifest");
+
+        m.assert();
+    }
+
+    #[test]
+    fn test_disk_cache_blob() {
+        let digest: Digest =
+            "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+                .parse()
+                .expect("Could not parse reference digest");
+
+        let m = mock("GET", format!("/v2/test/cache/blobs/{}", digest).as_str())
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body("hello world")
+            .expect(1)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_disk_cache(temp_dir("blob"));
+        assert_eq!(registry.cache_size_bytes().unwrap(), 0);
+
+        for _ in 0..2 {
+            let mut content = String::new();
+            registry
+                .fetch_blob("test/cache", &digest)
+                .expect("Could not fetch blob")
+                .read_to_string(&mut content)
+                .expect("Could not read blob");
+            assert_eq!(content, "hello world");
+        }
+
+        m.assert();
+        assert_eq!(registry.cache_size_bytes().unwrap(), 11);
+
+        registry.clear_cache().expect("Could not clear cache");
+        assert_eq!(registry.cache_size_bytes().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_disk_cache_blob_digest_mismatch() {
+        let digest: Digest =
+            "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+                .parse()
+                .expect("Could not parse reference digest");
+
+        let _m = mock(
+            "GET",
+            format!("/v2/test/tampered/blobs/{}", digest).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body("hello tampered world")
+        .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_disk_cache(temp_dir("tampered"));
+
+        match registry.fetch_blob("test/tampered", &digest) {
+            Err(RegistryError::DigestMismatch(DigestMismatchError { expected, .. })) => {
+                assert_eq!(expected, digest)
+            }
+            Err(e) => panic!("unexpected error: {:?}", e),
+            Ok(_) => panic!("tampered blob was accepted"),
+        }
+        assert_eq!(registry.cache_size_bytes().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_disk_cache_manifest() {
+        let m = mock("GET", "/v2/test/cache/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .expect(1)
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_disk_cache(temp_dir("manifest"));
+
+        for _ in 0..2 {
+            let response = registry
+                .get_manifest_with_content_type("test/cache", "latest")
+                .expect("Could not get manifest");
+            assert_eq!(
+                response.content_type,
+                "application/vnd.docker.distribution.manifest.v2+json"
+            );
+            assert_eq!(
+                response.digest,
+                Digest::compute(DigestAlgorithm::Sha256, MANIFEST.as_bytes())
+            );
+        }
+
+        m.assert();
+        registry.clear_cache().expect("Could not clear cache");
+    }
+
+    #[test]
+    fn test_disk_cache_manifest_invalid_name() {
+        let root = temp_dir("traversal").join("cache");
+        let cache = DiskCache::new(root.clone());
+
+        for (name, reference) in &[
+            ("../../../escape", "latest"),
+            ("/escape", "latest"),
+            ("test/../../../../escape", "latest"),
+            ("test/cache", "../../../../escape"),
+            ("Test/Cache", "latest"),
+        ] {
+            cache
+                .put_manifest(
+                    "https://registry.example.com",
+                    name,
+                    reference,
+                    "application/vnd.docker.distribution.manifest.v2+json",
+                    MANIFEST.as_bytes(),
+                )
+                .expect("Could not skip invalid manifest");
+            assert!(cache
+                .get_manifest("https://registry.example.com", name, reference)
+                .is_none());
+        }
+
+        assert!(!root.exists());
+        assert!(!root.parent().unwrap().join("escape").exists());
+    }
+
+    #[test]
+    fn test_registry_credential_store() {
+        let registry = Registry::new("https://registry.example.com");
+        assert_eq!(registry.credentials(), None);
+
+        let store = StaticCredentialStore::new(Credentials::new("user", "pass"));
+        let registry = registry.with_credential_store(Arc::new(store));
+        assert_eq!(
+            registry.credentials(),
+            Some(Credentials::new("user", "pass"))
+        );
+    }
+
+    #[test]
+    fn test_registry_basic_auth() {
+        let m = mock("GET", "/v2/test/auth/basic")
+            .match_header("authorization", "Basic dXNlcjpwYXNz")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_auth(RegistryAuth::Basic {
+            username: "user".into(),
+            password: "pass".into(),
+        });
+        assert_eq!(
+            registry.credentials(),
+            Some(Credentials::new("user", "pass"))
+        );
+
+        let url = format!("{}/v2/test/auth/basic", registry.url);
+        registry
+            .get(&url, None)
+            .expect("Could not get response with Basic authentication");
+        m.assert();
+    }
+
+    #[test]
+    fn test_registry_bearer_auth() {
+        let m = mock("GET", "/v2/test/auth/bearer")
+            .match_header("authorization", "Bearer secret-token")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url())
+            .with_auth(RegistryAuth::Bearer("secret-token".into()));
+        assert_eq!(registry.credentials(), None);
+
+        let url = format!("{}/v2/test/auth/bearer", registry.url);
+        registry
+            .get(&url, None)
+            .expect("Could not get response with Bearer authentication");
+        m.assert();
+    }
+
+    #[test]
+    fn test_registry_docker_token_service() {
+        use mockito::Matcher;
+
+        let challenge = format!(
+            r#"Bearer realm="{}/token",service="registry.test",scope="repository:test/token:pull""#,
+            mockito::server_url()
+        );
+        let unauthorized = mock("GET", "/v2/test/token/manifests/latest")
+            .match_header("authorization", Matcher::Missing)
+            .with_status(401)
+            .with_header("www-authenticate", &challenge)
+            .create();
+        let token = mock("GET", "/token")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("service".into(), "registry.test".into()),
+                Matcher::UrlEncoded("scope".into(), "repository:test/token:pull".into()),
+            ]))
+            .match_header("authorization", "Basic dXNlcjpwYXNz")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"token": "secret-token"}"#)
+            .create();
+        let authorized = mock("GET", "/v2/test/token/manifests/latest")
+            .match_header("authorization", "Bearer secret-token")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let registry = Registry::new(&mockito::server_url()).with_auth(
+            RegistryAuth::DockerTokenService(Some(Credentials::new("user", "pass"))),
+        );
+
+        let url = format!("{}/v2/test/token/manifests/latest", registry.url);
+        registry
+            .get(&url, None)
+            .expect("Could not get response through the token service");
+
+        unauthorized.assert();
+        token.assert();
+        authorized.assert();
+    }
+
+    #[test]
+    fn test_check_connectivity() {
+        let _alive = mock("GET", "/alive/v2/")
+            .with_status(401)
+            .with_header(
+                "www-authenticate",
+                r#"Bearer realm="https://auth.test/token""#,
+            )
+            .create();
+        let _down = mock("GET", "/down/v2/").with_status(503).create();
+
+        let registry = Registry::new(&format!("{}/alive", mockito::server_url()));
+        registry
+            .check_connectivity()
+            .expect("Registry asking for authentication is not reachable");
+
+        let registry = Registry::new(&format!("{}/down", mockito::server_url()));
+        match registry.check_connectivity() {
+            Err(RegistryError::HttpStatus { status, .. }) => assert_eq!(status, 503),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_auth() {
+        use mockito::Matcher;
+
+        let challenge = format!(
+            r#"Bearer realm="{}/check-auth/token",service="registry.test""#,
+            mockito::server_url()
+        );
+        let _unauthorized = mock("GET", "/check-auth/v2/")
+            .match_header("authorization", Matcher::Missing)
+            .with_status(401)
+            .with_header("www-authenticate", &challenge)
+            .create();
+        let _token = mock("GET", "/check-auth/token")
+            .match_query(Matcher::Any)
+            .match_header("authorization", "Basic dXNlcjpwYXNz")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"token": "secret-token"}"#)
+            .create();
+        let _denied = mock("GET", "/check-auth/token")
+            .match_query(Matcher::Any)
+            .match_header("authorization", Matcher::Any)
+            .with_status(401)
+            .create();
+        let _authorized = mock("GET", "/check-auth/v2/")
+            .match_header("authorization", "Bearer secret-token")
+            .with_status(200)
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .create();
+
+        let url = format!("{}/check-auth", mockito::server_url());
+
+        let registry = Registry::new(&url).with_auth(RegistryAuth::DockerTokenService(Some(
+            Credentials::new("user", "pass"),
+        )));
+        registry
+            .check_auth()
+            .expect("Credentials were not accepted");
+
+        let registry = Registry::new(&url).with_auth(RegistryAuth::DockerTokenService(Some(
+            Credentials::new("user", "wrong"),
+        )));
+        match registry.check_auth() {
+            Err(RegistryError::CouldNotAuthenticate) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_registry_auth_debug_redacted() {
+        let auth = RegistryAuth::Basic {
+            username: "user".into(),
+            password: "pass".into(),
+        };
+        assert!(!format!("{:?}", auth).contains("pass"));
+        assert!(!format!("{:?}", RegistryAuth::Bearer("token".into())).contains("token"));
+
+        let auth = RegistryAuth::DockerTokenService(Some(Credentials::new("user", "pass")));
+        assert!(!format!("{:?}", auth).contains("pass"));
+    }
+
+    #[test]
+    fn test_get_manifest_for_platform() {
+        use crate::image::manifest::{ManifestError, ManifestListV2_2};
+        use crate::image::TestImageSelector;
+
+        const MANIFEST_LIST: &str = include_str!("../image/test/manifest-list-v2-2.test.json");
+
+        // Point the amd64 entry at the manifest served below, so that it
+        // matches its digest.
+        let manifest_list_body = MANIFEST_LIST.replace(
+            "sha256:5b0bcabd1ed22e9fb1310cf6c2dec7cdef19f0ad69efa1f392e94a4333501270",
+            MANIFEST_DIGEST,
+        );
+
+        let _list = mock("GET", "/v2/test/platform/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.list.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(&manifest_list_body)
+            .create();
+        let _ppc64le = mock(
+            "GET",
+            "/v2/test/platform/manifests/sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f",
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(MANIFEST)
+        .create();
+        let amd64 = mock(
+            "GET",
+            format!("/v2/test/platform/manifests/{}", MANIFEST_DIGEST).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(MANIFEST)
+        .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let image = registry
+            .image::<TestImageSelector>("test/platform", "latest")
+            .expect("Could not get image");
+
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(&manifest_list_body).expect("Could not parse manifest list");
+        let manifest = manifest_list
+            .get_manifest_for_platform("amd64", "linux", &image)
+            .expect("Could not get manifest for platform");
+        assert_eq!(manifest.layers.len(), 3);
+        amd64.assert();
+
+        match manifest_list.get_manifest_for_platform("arm64", "linux", &image) {
+            Err(RegistryError::ManifestError(ManifestError::NoMatchingPlatformFound)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_image_resolves_image_index() {
+        use crate::image::TestImageSelector;
+        use mockito::Matcher;
+
+        // Point the ppc64le entry at the manifest served below, so that it
+        // matches its digest.
+        let index = include_str!("../image/test/image-index-v1.test.json").replace(
+            "sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f",
+            MANIFEST_DIGEST,
+        );
+
+        let _index = mock("GET", "/v2/test/index/manifests/latest")
+            .with_status(200)
+            .with_header("content-type", "application/vnd.oci.image.index.v1+json")
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(&index)
+            .create();
+        let ppc64le = mock(
+            "GET",
+            format!("/v2/test/index/manifests/{}", MANIFEST_DIGEST).as_str(),
+        )
+        .match_header(
+            "accept",
+            Matcher::Regex(r"application/vnd\.oci\.image\.manifest\.v1\+json".into()),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(MANIFEST)
+        .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let image = registry
+            .image::<TestImageSelector>("test/index", "latest")
+            .expect("Could not get image");
+
+        match image.manifest() {
+            ManifestV2::Schema2(m) => assert_eq!(m.layers.len(), 3),
+            other => panic!("Image index was not resolved: {:?}", other),
+        }
+        ppc64le.assert();
+    }
+
+    #[test]
+    fn test_cached_image_layer() {
+        use crate::image::manifest::{Layer, LayerMediaType, LayerV2_2};
+        use crate::image::{CachedImage, TestImageSelector};
+        use crate::store::FilesystemContentStore;
+
+        let _m = mock("GET", "/v2/test/cached/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(MANIFEST)
+            .create();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "hello", &b"hello"[..])
+            .expect("Could not build layer");
+        let blob = builder.into_inner().expect("Could not build layer");
+        let layer = LayerV2_2::for_blob(LayerMediaType::Tar, &blob).unwrap();
+
+        let m = mock(
+            "GET",
+            format!("/v2/test/cached/blobs/{}", layer.digest()).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(&blob)
+        .expect(1)
+        .create();
+
+        let dir = temp_dir("cached-image");
+        let registry = Registry::new(&mockito::server_url());
+        let image = CachedImage::new(
+            registry
+                .image::<TestImageSelector>("test/cached", "latest")
+                .expect("Could not get image"),
+            Box::new(FilesystemContentStore::new(&dir)),
+        );
+
+        for _ in 0..2 {
+            let mut archive = image.get_layer(&layer).expect("Could not get layer");
+            let entry = archive
+                .entries()
+                .expect("Could not read layer")
+                .next()
+                .expect("Layer is empty")
+                .expect("Could not read layer entry");
+            assert_eq!(entry.path().unwrap().to_str(), Some("hello"));
+        }
+
+        m.assert();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_image_async() {
+        use crate::image::manifest::{ConfigV2_2, Layer, LayerMediaType, LayerV2_2};
+        use crate::image::TestImageSelector;
+        use std::io::Read;
+
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "hello", &b"hello"[..])
+            .expect("Could not build layer");
+        let blob = builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .expect("Could not build layer");
+        let layer = LayerV2_2::for_blob(LayerMediaType::TarGz, &blob).unwrap();
+        let served =
+            ManifestV2_2::new(ConfigV2_2::for_docker_json_blob(b"{}"), vec![layer.clone()]);
+
+        let _m = mock("GET", "/v2/test/async/manifests/latest")
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "application/vnd.docker.distribution.manifest.v2+json",
+            )
+            .with_header("docker-distribution-api-version", "registry/2.0")
+            .with_body(serde_json::to_string(&served).unwrap())
+            .expect(2)
+            .create();
+        let _b = mock(
+            "GET",
+            format!("/v2/test/async/blobs/{}", layer.digest()).as_str(),
+        )
+        .with_status(200)
+        .with_header("docker-distribution-api-version", "registry/2.0")
+        .with_body(&blob)
+        .create();
+
+        let registry = Registry::new(&mockito::server_url());
+        let image = registry
+            .image::<TestImageSelector>("test/async", "latest")
+            .expect("Could not get image");
+
+        let manifest = match image
+            .get_manifest_async()
+            .await
+            .expect("Could not get manifest")
+        {
+            ManifestV2::Schema2(manifest) => manifest,
+            other => panic!("Unexpected manifest: {:?}", other),
+        };
+        assert_eq!(manifest.layers, vec![layer]);
+
+        let mut archive = image
+            .get_layer_async(&manifest.layers[0])
+            .await
+            .expect("Could not get layer");
+        let mut entry = archive
+            .entries()
+            .expect("Could not read layer")
+            .next()
+            .expect("Layer is empty")
+            .expect("Could not read layer entry");
+
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .expect("Could not read layer entry");
+        assert_eq!(entry.path().unwrap().to_str(), Some("hello"));
+        assert_eq!(content, "hello");
     }
 }
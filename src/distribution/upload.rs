@@ -0,0 +1,154 @@
+use crate::distribution::{Registry, RegistryError};
+use crate::image::manifest::Digest;
+
+use reqwest::header::{
+    HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LOCATION,
+};
+use reqwest::{Method, StatusCode, Url};
+
+use std::io::Read;
+
+/// A blob upload in progress, as started by [Registry::start_upload].
+///
+/// Data is sent in chunks with [UploadSession::upload_chunk], and the upload
+/// is completed with [UploadSession::finish].
+#[derive(Debug)]
+pub struct UploadSession<'a> {
+    registry: &'a Registry,
+    location: String,
+    offset: usize,
+}
+
+impl<'a> UploadSession<'a> {
+    pub(crate) fn start(registry: &'a Registry, name: &str) -> Result<Self, RegistryError> {
+        let url = format!("{}/v2/{}/blobs/uploads/", registry.url, name);
+
+        let response = expect_status(
+            registry.send(Method::POST, &url, None, None)?,
+            StatusCode::ACCEPTED,
+        )?;
+
+        Ok(UploadSession {
+            location: location(registry, &response)?,
+            registry,
+            offset: 0,
+        })
+    }
+
+    /// Return the URL the next request of the upload is sent to.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Return the number of bytes uploaded so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Upload the next chunk of the blob.
+    ///
+    /// Chunks are uploaded in order, each starting where the previous one
+    /// ended.
+    pub fn upload_chunk(&mut self, data: &[u8]) -> Result<(), RegistryError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/octet-stream"),
+        );
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(data.len()));
+        headers.insert(
+            CONTENT_RANGE,
+            format!("{}-{}", self.offset, self.offset + data.len() - 1)
+                .parse()
+                .expect("Content-Range is a valid header value"),
+        );
+
+        let response = expect_status(
+            self.registry
+                .send(Method::PATCH, &self.location, Some(&headers), Some(data))?,
+            StatusCode::ACCEPTED,
+        )?;
+
+        self.location = location(self.registry, &response)?;
+        self.offset += data.len();
+
+        Ok(())
+    }
+
+    /// Upload all data from `reader` in chunks of `chunk_size` bytes.
+    ///
+    /// Each chunk is filled completely before it is uploaded, regardless of
+    /// how much data a single read returns. `buf` is used to hold the chunks,
+    /// so it can be reused across uploads.
+    pub(crate) fn upload_reader<R: Read>(
+        &mut self,
+        reader: R,
+        buf: &mut Vec<u8>,
+        chunk_size: usize,
+    ) -> Result<(), RegistryError> {
+        let mut reader = reader.take(chunk_size as u64);
+
+        loop {
+            buf.clear();
+            reader.read_to_end(buf).map_err(RegistryError::IoError)?;
+            if buf.is_empty() {
+                return Ok(());
+            }
+            self.upload_chunk(buf)?;
+            reader.set_limit(chunk_size as u64);
+        }
+    }
+
+    /// Complete the upload.
+    ///
+    /// The registry verifies the uploaded content against `digest`.
+    pub fn finish(self, digest: &Digest) -> Result<(), RegistryError> {
+        let mut url = Url::parse(&self.location)
+            .map_err(|_| RegistryError::InvalidResponse(self.location.clone()))?;
+        url.query_pairs_mut()
+            .append_pair("digest", &digest.to_string());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(0));
+
+        expect_status(
+            self.registry
+                .send(Method::PUT, url.as_str(), Some(&headers), None)?,
+            StatusCode::CREATED,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Check that a response of the upload protocol has the expected status.
+pub(crate) fn expect_status(
+    response: Result<reqwest::Response, reqwest::Response>,
+    status: StatusCode,
+) -> Result<reqwest::Response, RegistryError> {
+    match response {
+        Ok(response) | Err(response) if response.status() != status => {
+            Err(RegistryError::UploadFailed(response.status()))
+        }
+        Ok(response) | Err(response) => Ok(response),
+    }
+}
+
+/// Return the absolute URL from the `Location` header of an upload response.
+fn location(registry: &Registry, response: &reqwest::Response) -> Result<String, RegistryError> {
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .ok_or_else(|| RegistryError::InvalidResponse("Missing Location header".into()))?;
+
+    if location.starts_with("http://") || location.starts_with("https://") {
+        Ok(location.into())
+    } else {
+        Ok(format!("{}{}", registry.url, location))
+    }
+}
@@ -0,0 +1,142 @@
+//! Helpers for applying image layers to a filesystem.
+
+use std::path::{Path, PathBuf};
+
+/// File name prefix of a whiteout.
+pub const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// File name of an opaque whiteout.
+pub const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// Return whether a path in a layer is an opaque whiteout.
+///
+/// An opaque whiteout is a file named `.wh..wh..opq`. It indicates that all
+/// siblings in its containing directory from lower layers are hidden, i.e. the
+/// entire content of the containing directory should be whited out before the
+/// content of the current layer is applied.
+///
+/// Only the file name is considered, so `.wh..wh..opq` occurring in a parent
+/// component does not make a path an opaque whiteout.
+///
+/// # Example
+/// ```
+///# use opencontainers::glue::is_opaque_whiteout;
+/// assert!(is_opaque_whiteout("a/b/.wh..wh..opq"));
+/// assert!(!is_opaque_whiteout("a/b/.wh.c"));
+/// ```
+pub fn is_opaque_whiteout<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .file_name()
+        .map_or(false, |name| name == OPAQUE_WHITEOUT)
+}
+
+/// Return the directory that is whited out by an opaque whiteout.
+///
+/// This is the parent directory of the `.wh..wh..opq` file. Returns `None` if
+/// `path` is not an opaque whiteout.
+///
+/// # Example
+/// ```
+///# use opencontainers::glue::get_opaque_whiteout_dir;
+///# use std::path::PathBuf;
+/// assert_eq!(get_opaque_whiteout_dir("a/b/.wh..wh..opq"), Some(PathBuf::from("a/b")));
+/// assert_eq!(get_opaque_whiteout_dir("a/b/c"), None);
+/// ```
+pub fn get_opaque_whiteout_dir<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+    let path = path.as_ref();
+
+    if !is_opaque_whiteout(path) {
+        return None;
+    }
+
+    path.parent().map(Path::to_path_buf)
+}
+
+/// Return the path that is whited out by a whiteout file.
+///
+/// A whiteout file `.wh.<name>` removes `<name>` from the same directory.
+/// Returns `None` if `path` is not a whiteout or is an opaque whiteout, see
+/// [get_opaque_whiteout_dir] for the latter.
+///
+/// # Example
+/// ```
+///# use opencontainers::glue::get_whiteout_path;
+///# use std::path::PathBuf;
+/// assert_eq!(get_whiteout_path("a/b/.wh.c"), Some(PathBuf::from("a/b/c")));
+/// assert_eq!(get_whiteout_path("a/b/.wh..wh..opq"), None);
+/// ```
+pub fn get_whiteout_path<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
+    let path = path.as_ref();
+
+    if is_opaque_whiteout(path) {
+        return None;
+    }
+
+    let name = path.file_name()?.to_str()?;
+    if !name.starts_with(WHITEOUT_PREFIX) || name.len() == WHITEOUT_PREFIX.len() {
+        return None;
+    }
+
+    Some(path.with_file_name(&name[WHITEOUT_PREFIX.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_opaque_whiteout() {
+        assert!(is_opaque_whiteout(".wh..wh..opq"));
+        assert!(is_opaque_whiteout("a/.wh..wh..opq"));
+        assert!(is_opaque_whiteout("/a/b/.wh..wh..opq"));
+
+        assert!(!is_opaque_whiteout("a"));
+        assert!(!is_opaque_whiteout("a/.wh.b"));
+        assert!(!is_opaque_whiteout("a/.wh..wh..opq.b"));
+        assert!(!is_opaque_whiteout("a/.wh..wh..opq/b"));
+        assert!(!is_opaque_whiteout(".wh..wh..opq/b/c"));
+    }
+
+    #[test]
+    fn test_get_opaque_whiteout_dir() {
+        assert_eq!(
+            get_opaque_whiteout_dir("a/b/.wh..wh..opq"),
+            Some(PathBuf::from("a/b"))
+        );
+        assert_eq!(
+            get_opaque_whiteout_dir("/a/.wh..wh..opq"),
+            Some(PathBuf::from("/a"))
+        );
+        assert_eq!(
+            get_opaque_whiteout_dir(".wh..wh..opq"),
+            Some(PathBuf::from(""))
+        );
+
+        assert_eq!(get_opaque_whiteout_dir("a/.wh..wh..opq/b"), None);
+        assert_eq!(get_opaque_whiteout_dir("a/b"), None);
+    }
+
+    #[test]
+    fn test_get_whiteout_path() {
+        assert_eq!(get_whiteout_path("a/b/.wh.c"), Some(PathBuf::from("a/b/c")));
+        assert_eq!(get_whiteout_path(".wh.a"), Some(PathBuf::from("a")));
+        assert_eq!(
+            get_whiteout_path("/a/.wh..hidden"),
+            Some(PathBuf::from("/a/.hidden"))
+        );
+
+        assert_eq!(get_whiteout_path("a/b/.wh..wh..opq"), None);
+        assert_eq!(get_whiteout_path("a/b/c"), None);
+        assert_eq!(get_whiteout_path("a/.wh."), None);
+        assert_eq!(get_whiteout_path("a/.wh.b/c"), None);
+    }
+
+    #[test]
+    fn test_opaque_whiteout_dir_is_parent() {
+        // The directory to clear is the one containing the opaque whiteout,
+        // not the whiteout file itself.
+        let dir = get_opaque_whiteout_dir("a/b/.wh..wh..opq").unwrap();
+        assert_eq!(dir, PathBuf::from("a/b"));
+        assert!(!is_opaque_whiteout(&dir));
+    }
+}
@@ -1,28 +1,35 @@
 //! Rust enums for Go (Golang) values of GOOS and GOARCH
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
 
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
 pub enum GoError {
-    #[fail(display = "Invalid GOOS string: {}", _0)]
+    #[error("Invalid GOOS string: {0}")]
     InvalidGoOs(String),
 
-    #[fail(display = "Invalid GOARCH string: {}", _0)]
+    #[error("Invalid GOARCH string: {0}")]
     InvalidGoArch(String),
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum GoOs {
+    Aix,
     Android,
     Darwin,
     Dragonfly,
     FreeBSD,
+    Hurd,
+    Illumos,
+    Ios,
+    Js,
     Linux,
     NaCl,
     NetBSD,
     OpenBSD,
     Plan9,
     Solaris,
+    Wasip1,
     Windows,
     ZOS,
 }
@@ -32,18 +39,24 @@ impl std::str::FromStr for GoOs {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "aix" => Ok(GoOs::Aix),
             "android" => Ok(GoOs::Android),
             "darwin" => Ok(GoOs::Darwin),
             "macos" => Ok(GoOs::Darwin),
-            "ios" => Ok(GoOs::Darwin),
             "dragonfly" => Ok(GoOs::Dragonfly),
             "freebsd" => Ok(GoOs::FreeBSD),
+            "hurd" => Ok(GoOs::Hurd),
+            "illumos" => Ok(GoOs::Illumos),
+            "ios" => Ok(GoOs::Ios),
+            "js" => Ok(GoOs::Js),
             "linux" => Ok(GoOs::Linux),
             "nacl" => Ok(GoOs::NaCl),
             "netbsd" => Ok(GoOs::NetBSD),
             "openbsd" => Ok(GoOs::OpenBSD),
             "plan9" => Ok(GoOs::Plan9),
             "solaris" => Ok(GoOs::Solaris),
+            "wasip1" => Ok(GoOs::Wasip1),
+            "wasi" => Ok(GoOs::Wasip1),
             "windows" => Ok(GoOs::Windows),
             "zos" => Ok(GoOs::ZOS),
             other => Err(GoError::InvalidGoOs(other.into())),
@@ -57,16 +70,22 @@ impl std::fmt::Display for GoOs {
             f,
             "{}",
             match self {
+                GoOs::Aix => "aix",
                 GoOs::Android => "android",
                 GoOs::Darwin => "darwin",
                 GoOs::Dragonfly => "dragonfly",
                 GoOs::FreeBSD => "freebsd",
+                GoOs::Hurd => "hurd",
+                GoOs::Illumos => "illumos",
+                GoOs::Ios => "ios",
+                GoOs::Js => "js",
                 GoOs::Linux => "linux",
                 GoOs::NaCl => "nacl",
                 GoOs::NetBSD => "netbsd",
                 GoOs::OpenBSD => "openbsd",
                 GoOs::Plan9 => "plan9",
                 GoOs::Solaris => "solaris",
+                GoOs::Wasip1 => "wasip1",
                 GoOs::Windows => "windows",
                 GoOs::ZOS => "zos",
             }
@@ -74,6 +93,35 @@ impl std::fmt::Display for GoOs {
     }
 }
 
+impl GoOs {
+    /// Return the name of the operating system as used by Rust, i.e. in
+    /// `std::env::consts::OS` and `cfg(target_os = "...")`.
+    ///
+    /// Returns `None` if Rust has no equivalent target OS.
+    pub fn to_rust_target_os(self) -> Option<&'static str> {
+        match self {
+            GoOs::Aix => Some("aix"),
+            GoOs::Android => Some("android"),
+            GoOs::Darwin => Some("macos"),
+            GoOs::Dragonfly => Some("dragonfly"),
+            GoOs::FreeBSD => Some("freebsd"),
+            GoOs::Hurd => Some("hurd"),
+            GoOs::Illumos => Some("illumos"),
+            GoOs::Ios => Some("ios"),
+            GoOs::Js => None,
+            GoOs::Linux => Some("linux"),
+            GoOs::NaCl => None,
+            GoOs::NetBSD => Some("netbsd"),
+            GoOs::OpenBSD => Some("openbsd"),
+            GoOs::Plan9 => None,
+            GoOs::Solaris => Some("solaris"),
+            GoOs::Wasip1 => Some("wasi"),
+            GoOs::Windows => Some("windows"),
+            GoOs::ZOS => Some("zos"),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for GoOs {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -103,6 +151,7 @@ pub enum GoArch {
     ARMbe,
     ARM64,
     ARM64be,
+    Loong64,
     PPC64,
     PPC64le,
     MIPS,
@@ -112,10 +161,13 @@ pub enum GoArch {
     MIPS64p32,
     MIPS64p32le,
     PPC,
+    RISCV,
+    RISCV64,
     S390,
     S390x,
     SPARC,
     SPARC64,
+    Wasm,
 }
 
 impl std::str::FromStr for GoArch {
@@ -133,6 +185,8 @@ impl std::str::FromStr for GoArch {
             "arm64" => Ok(GoArch::ARM64),
             "aarch64" => Ok(GoArch::ARM64),
             "arm64be" => Ok(GoArch::ARM64be),
+            "loong64" => Ok(GoArch::Loong64),
+            "loongarch64" => Ok(GoArch::Loong64),
             "ppc64" => Ok(GoArch::PPC64),
             "ppc64le" => Ok(GoArch::PPC64le),
             "mips" => Ok(GoArch::MIPS),
@@ -143,10 +197,14 @@ impl std::str::FromStr for GoArch {
             "mips64p32le" => Ok(GoArch::MIPS64p32le),
             "ppc" => Ok(GoArch::PPC),
             "powerpc" => Ok(GoArch::PPC),
+            "riscv" => Ok(GoArch::RISCV),
+            "riscv64" => Ok(GoArch::RISCV64),
             "s390" => Ok(GoArch::S390),
             "s390x" => Ok(GoArch::S390x),
             "sparc" => Ok(GoArch::SPARC),
             "sparc64" => Ok(GoArch::SPARC64),
+            "wasm" => Ok(GoArch::Wasm),
+            "wasm32" => Ok(GoArch::Wasm),
             other => Err(GoError::InvalidGoArch(other.into())),
         }
     }
@@ -165,6 +223,7 @@ impl std::fmt::Display for GoArch {
                 GoArch::ARMbe => "armbe",
                 GoArch::ARM64 => "arm64",
                 GoArch::ARM64be => "arm64be",
+                GoArch::Loong64 => "loong64",
                 GoArch::PPC64 => "ppc64",
                 GoArch::PPC64le => "ppc64le",
                 GoArch::MIPS => "mips",
@@ -174,15 +233,92 @@ impl std::fmt::Display for GoArch {
                 GoArch::MIPS64p32 => "mips64p32",
                 GoArch::MIPS64p32le => "mips64p32le",
                 GoArch::PPC => "ppc",
+                GoArch::RISCV => "riscv",
+                GoArch::RISCV64 => "riscv64",
                 GoArch::S390 => "s390",
                 GoArch::S390x => "s390x",
                 GoArch::SPARC => "sparc",
                 GoArch::SPARC64 => "sparc64",
+                GoArch::Wasm => "wasm",
             }
         )
     }
 }
 
+impl GoArch {
+    /// Return the name of the architecture as used by Rust, i.e. in
+    /// `std::env::consts::ARCH` and `cfg(target_arch = "...")`.
+    ///
+    /// Rust does not encode the endianness in the architecture name, so e.g.
+    /// both `mips64` and `mips64le` map to `mips64`. Returns `None` if Rust has
+    /// no equivalent target architecture.
+    pub fn to_rust_target_arch(self) -> Option<&'static str> {
+        match self {
+            GoArch::I386 => Some("x86"),
+            GoArch::AMD64 => Some("x86_64"),
+            GoArch::AMD64p32 => None,
+            GoArch::ARM | GoArch::ARMbe => Some("arm"),
+            GoArch::ARM64 | GoArch::ARM64be => Some("aarch64"),
+            GoArch::Loong64 => Some("loongarch64"),
+            GoArch::PPC64 | GoArch::PPC64le => Some("powerpc64"),
+            GoArch::MIPS | GoArch::MIPSle => Some("mips"),
+            GoArch::MIPS64 | GoArch::MIPS64le => Some("mips64"),
+            GoArch::MIPS64p32 | GoArch::MIPS64p32le => None,
+            GoArch::PPC => Some("powerpc"),
+            GoArch::RISCV => Some("riscv32"),
+            GoArch::RISCV64 => Some("riscv64"),
+            GoArch::S390 => None,
+            GoArch::S390x => Some("s390x"),
+            GoArch::SPARC => Some("sparc"),
+            GoArch::SPARC64 => Some("sparc64"),
+            GoArch::Wasm => Some("wasm32"),
+        }
+    }
+
+    /// Map a Rust target architecture name to the architecture.
+    ///
+    /// As Rust architecture names do not encode the endianness, bi-endian
+    /// architectures map to the variant Go names without a suffix, e.g. `mips`
+    /// to big-endian [GoArch::MIPS] and `arm` to little-endian [GoArch::ARM].
+    /// Use [GoArch::current] for the architecture of the running program.
+    pub fn from_rust_target_arch(s: &str) -> Option<GoArch> {
+        match s {
+            "x86" => Some(GoArch::I386),
+            "x86_64" => Some(GoArch::AMD64),
+            "arm" => Some(GoArch::ARM),
+            "aarch64" => Some(GoArch::ARM64),
+            "loongarch64" => Some(GoArch::Loong64),
+            "powerpc64" => Some(GoArch::PPC64),
+            "mips" => Some(GoArch::MIPS),
+            "mips64" => Some(GoArch::MIPS64),
+            "powerpc" => Some(GoArch::PPC),
+            "riscv32" => Some(GoArch::RISCV),
+            "riscv64" => Some(GoArch::RISCV64),
+            "s390x" => Some(GoArch::S390x),
+            "sparc" => Some(GoArch::SPARC),
+            "sparc64" => Some(GoArch::SPARC64),
+            "wasm32" => Some(GoArch::Wasm),
+            _ => None,
+        }
+    }
+
+    /// Return the architecture the running program was compiled for, taking
+    /// the target endianness into account.
+    pub fn current() -> Option<GoArch> {
+        let arch = Self::from_rust_target_arch(std::env::consts::ARCH)?;
+        let little_endian = cfg!(target_endian = "little");
+
+        Some(match arch {
+            GoArch::PPC64 if little_endian => GoArch::PPC64le,
+            GoArch::MIPS if little_endian => GoArch::MIPSle,
+            GoArch::MIPS64 if little_endian => GoArch::MIPS64le,
+            GoArch::ARM if !little_endian => GoArch::ARMbe,
+            GoArch::ARM64 if !little_endian => GoArch::ARM64be,
+            other => other,
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for GoArch {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -202,3 +338,117 @@ impl Serialize for GoArch {
         serializer.collect_str(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All values of `$GOOS` known to Go 1.22.
+    const GOOS: &[&str] = &[
+        "aix",
+        "android",
+        "darwin",
+        "dragonfly",
+        "freebsd",
+        "hurd",
+        "illumos",
+        "ios",
+        "js",
+        "linux",
+        "nacl",
+        "netbsd",
+        "openbsd",
+        "plan9",
+        "solaris",
+        "wasip1",
+        "windows",
+        "zos",
+    ];
+
+    /// All values of `$GOARCH` known to Go 1.22.
+    const GOARCH: &[&str] = &[
+        "386",
+        "amd64",
+        "amd64p32",
+        "arm",
+        "armbe",
+        "arm64",
+        "arm64be",
+        "loong64",
+        "mips",
+        "mipsle",
+        "mips64",
+        "mips64le",
+        "mips64p32",
+        "mips64p32le",
+        "ppc",
+        "ppc64",
+        "ppc64le",
+        "riscv",
+        "riscv64",
+        "s390",
+        "s390x",
+        "sparc",
+        "sparc64",
+        "wasm",
+    ];
+
+    #[test]
+    fn test_goos_roundtrip() {
+        for os in GOOS {
+            assert_eq!(&os.parse::<GoOs>().unwrap().to_string(), os);
+        }
+    }
+
+    #[test]
+    fn test_goarch_roundtrip() {
+        for arch in GOARCH {
+            assert_eq!(&arch.parse::<GoArch>().unwrap().to_string(), arch);
+        }
+    }
+
+    #[test]
+    fn test_rust_target_arch() {
+        for arch in GOARCH {
+            let arch: GoArch = arch.parse().unwrap();
+            if let Some(rust_arch) = arch.to_rust_target_arch() {
+                let mapped = GoArch::from_rust_target_arch(rust_arch).unwrap();
+                assert_eq!(mapped.to_rust_target_arch(), Some(rust_arch));
+            }
+        }
+
+        assert_eq!(GoArch::PPC64le.to_rust_target_arch(), Some("powerpc64"));
+        assert_eq!(
+            GoArch::from_rust_target_arch("powerpc64"),
+            Some(GoArch::PPC64)
+        );
+        assert_eq!(GoArch::from_rust_target_arch("avr"), None);
+        assert_eq!(
+            GoArch::current().and_then(GoArch::to_rust_target_arch),
+            Some(std::env::consts::ARCH)
+        );
+    }
+
+    #[test]
+    fn test_rust_target_os() {
+        assert_eq!(GoOs::Darwin.to_rust_target_os(), Some("macos"));
+        assert_eq!(GoOs::Wasip1.to_rust_target_os(), Some("wasi"));
+        assert_eq!(GoOs::Plan9.to_rust_target_os(), None);
+
+        for os in GOOS {
+            let os: GoOs = os.parse().unwrap();
+            if let Some(rust_os) = os.to_rust_target_os() {
+                assert_eq!(rust_os.parse::<GoOs>().unwrap(), os);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rust_aliases() {
+        assert_eq!("macos".parse::<GoOs>().unwrap(), GoOs::Darwin);
+        assert_eq!("x86_64".parse::<GoArch>().unwrap(), GoArch::AMD64);
+        assert_eq!("aarch64".parse::<GoArch>().unwrap(), GoArch::ARM64);
+        assert_eq!("loongarch64".parse::<GoArch>().unwrap(), GoArch::Loong64);
+        assert_eq!("wasm32".parse::<GoArch>().unwrap(), GoArch::Wasm);
+    }
+}
@@ -1,33 +1,142 @@
 use pest::Parser;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::Digest as _;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::Read;
 use std::ops::Deref;
 use std::str::FromStr;
+use thiserror::Error;
 
 use crate::distribution::RegistryError;
-use crate::image::{go, Image, ImageSelector};
+use crate::image::spec::{ImageSpecError, ImageV1};
+use crate::image::{go, CurrentPlatformSelector, Image, ImageSelector};
 
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
 pub enum ManifestError {
-    #[fail(display = "JSON Error: {:?}", _0)]
-    JsonError(serde_json::Error),
+    #[error("JSON Error: {0}")]
+    JsonError(#[source] serde_json::Error),
 
-    #[fail(display = "Invalid Schema Version: {}", _0)]
+    #[error("Invalid Schema Version: {0}")]
     InvalidSchemaVersion(u64),
 
-    #[fail(display = "Invalid (unknown) Media Type: {}", _0)]
+    #[error("Invalid (unknown) Media Type: {0}")]
     InvalidMediaType(String),
 
-    #[fail(display = "Parsing digest failed: '{}' ({:?})", _0, _1)]
-    DigestParseFailed(String, #[cause] pest::error::Error<Rule>),
+    #[error("Parsing digest failed: '{0}'\n{1}")]
+    DigestParseFailed(String, #[source] pest::error::Error<Rule>),
 
-    #[fail(display = "Invalid digest algorithm: {}", _0)]
+    #[error("Invalid digest algorithm: {0}")]
     InvalidDigestAlgorithm(String),
 
-    #[fail(display = "Could not find manifest for current platform")]
+    #[error("Invalid {0} digest hex: '{1}'")]
+    InvalidDigestHex(DigestAlgorithm, String),
+
+    #[error("Could not find manifest for current platform")]
     NoMatchingPlatformFound,
+
+    #[error("IO Error: {0}")]
+    IoError(#[source] std::io::Error),
+
+    #[error("Not an image manifest: {0:?}")]
+    NotAnImageManifest(ManifestV2Schema),
+
+    #[error("Unexpected Manifest Schema: {0:?}")]
+    UnexpectedSchema(ManifestV2Schema),
+}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(error: std::io::Error) -> Self {
+        ManifestError::IoError(error)
+    }
+}
+
+impl From<ManifestError> for std::io::Error {
+    fn from(error: ManifestError) -> Self {
+        match error {
+            ManifestError::IoError(error) => error,
+            error => std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ManifestValidationError {
+    #[error("Manifest list contains no manifests")]
+    EmptyManifestList,
+
+    #[error("Duplicate platforms in manifest list: {0:?}")]
+    DuplicatePlatforms(Vec<(ManifestPlatformV2_2, Vec<usize>)>),
+}
+
+#[derive(Debug, Error)]
+pub enum ManifestBuildError {
+    #[error("Unsupported schema version: {0}")]
+    UnsupportedSchemaVersion(u64),
+
+    #[error("Cannot create a layer descriptor for an empty blob")]
+    EmptyLayer,
+
+    #[error("JSON Error: {0:?}")]
+    JsonError(serde_json::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum PlatformParseError {
+    #[error("Invalid platform specification: '{0}'")]
+    InvalidFormat(String),
+
+    #[error("Invalid operating system: '{0}'")]
+    InvalidOs(String),
+
+    #[error("Invalid architecture: '{0}'")]
+    InvalidArch(String),
+}
+
+/// Content did not match the digest it was expected to have.
+///
+/// `context` names the content, e.g. `manifest` or `layer 0`.
+#[derive(Debug, Error)]
+#[error("Digest mismatch for {context}: expected {expected}, got {actual}")]
+pub struct DigestMismatchError {
+    pub expected: Digest,
+    pub actual: Digest,
+    pub context: String,
+}
+
+impl DigestMismatchError {
+    /// Compare an actual digest against an expected digest.
+    pub(crate) fn compare(expected: &Digest, actual: Digest, context: &str) -> Result<(), Self> {
+        if &actual != expected {
+            return Err(DigestMismatchError {
+                expected: expected.clone(),
+                actual,
+                context: context.into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Compare the digest of `data` against an expected digest.
+    pub(crate) fn check(expected: &Digest, data: &[u8], context: &str) -> Result<(), Self> {
+        Self::compare(expected, Digest::compute(expected.algorithm, data), context)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DiffIdError {
+    #[error("Manifest has {layers} layers, but config lists {diff_ids} DiffIDs")]
+    LayerCountMismatch { layers: usize, diff_ids: usize },
 }
 
+/// Digest of the gzip-compressed empty tar archive, which Docker uses as the
+/// layer of images without any filesystem content.
+pub const EMPTY_LAYER_DIGEST: &str =
+    "sha256:a3ed95caeb02ffe68cdd9fd84406680ae93d633cb16422d00e8a7c22955b46d4";
+
 /// Helper struct to determine Image Manifest Schema.
 #[derive(Debug, Deserialize)]
 struct ManifestSchemaOnlyV2 {
@@ -64,6 +173,17 @@ pub trait Layer {
 
     /// Return the media type of the layer, if available
     fn media_type(&self) -> Option<&LayerMediaType>;
+
+    /// Return the size of the layer in bytes, if available
+    fn size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Return URLs the layer may be fetched from instead of the registry, if
+    /// any
+    fn urls(&self) -> Option<&[String]> {
+        None
+    }
 }
 
 impl Layer for Box<dyn Layer> {
@@ -74,9 +194,18 @@ impl Layer for Box<dyn Layer> {
     fn media_type(&self) -> Option<&LayerMediaType> {
         self.deref().media_type()
     }
+
+    fn size(&self) -> Option<usize> {
+        self.deref().size()
+    }
+
+    fn urls(&self) -> Option<&[String]> {
+        self.deref().urls()
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[non_exhaustive]
 pub enum LayerMediaType {
     // application/vnd.oci.image.layer.v1.tar
     Tar,
@@ -92,6 +221,12 @@ pub enum LayerMediaType {
     // application/vnd.docker.image.rootfs.foreign.diff.tar.gzip
     NondistributableTarGz,
 
+    // application/vnd.oci.image.layer.v1.tar+zstd
+    TarZstd,
+
+    // application/vnd.oci.image.layer.nondistributable.v1.tar+zstd
+    NondistributableTarZstd,
+
     /// An encountered mediaType that is unknown to the implementation MUST be ignored.
     Other(String),
 }
@@ -104,22 +239,67 @@ impl LayerMediaType {
             LayerMediaType::TarGz => true,
             LayerMediaType::NondistributableTar => false,
             LayerMediaType::NondistributableTarGz => false,
+            LayerMediaType::TarZstd => true,
+            LayerMediaType::NondistributableTarZstd => false,
             // Regard any other media types as distributable by default
             LayerMediaType::Other(_) => true,
         }
     }
 
     /// Return if media type is gzipped
+    ///
+    /// Unknown media types are assumed to be gzipped.
+    #[deprecated(note = "use `is_compressed` instead, which is false for unknown media types")]
     pub fn is_gzipped(&self) -> bool {
         match self {
             LayerMediaType::Tar => false,
             LayerMediaType::TarGz => true,
             LayerMediaType::NondistributableTar => false,
             LayerMediaType::NondistributableTarGz => true,
+            LayerMediaType::TarZstd => false,
+            LayerMediaType::NondistributableTarZstd => false,
             // Assume other media types are gzipped.
             LayerMediaType::Other(_) => true,
         }
     }
+
+    /// Return if media type is known to be compressed
+    ///
+    /// This is false for [LayerMediaType::Other], as the compression of
+    /// unknown media types cannot be determined.
+    pub fn is_compressed(&self) -> bool {
+        match self {
+            LayerMediaType::Tar => false,
+            LayerMediaType::TarGz => true,
+            LayerMediaType::NondistributableTar => false,
+            LayerMediaType::NondistributableTarGz => true,
+            LayerMediaType::TarZstd => true,
+            LayerMediaType::NondistributableTarZstd => true,
+            LayerMediaType::Other(_) => false,
+        }
+    }
+
+    /// Return if media type is zstd-compressed
+    pub fn is_zstd(&self) -> bool {
+        match self {
+            LayerMediaType::TarZstd => true,
+            LayerMediaType::NondistributableTarZstd => true,
+            _ => false,
+        }
+    }
+
+    /// Return a short name of the media type for display, e.g. `tar+gzip`.
+    fn short_name(&self) -> &str {
+        match self {
+            LayerMediaType::Tar => "tar",
+            LayerMediaType::TarGz => "tar+gzip",
+            LayerMediaType::NondistributableTar => "nondistributable tar",
+            LayerMediaType::NondistributableTarGz => "nondistributable tar+gzip",
+            LayerMediaType::TarZstd => "tar+zstd",
+            LayerMediaType::NondistributableTarZstd => "nondistributable tar+zstd",
+            LayerMediaType::Other(media_type) => media_type,
+        }
+    }
 }
 
 impl std::str::FromStr for LayerMediaType {
@@ -139,6 +319,10 @@ impl std::str::FromStr for LayerMediaType {
             "application/vnd.docker.image.rootfs.foreign.diff.tar.gzip" => {
                 LayerMediaType::NondistributableTarGz
             }
+            "application/vnd.oci.image.layer.v1.tar+zstd" => LayerMediaType::TarZstd,
+            "application/vnd.oci.image.layer.nondistributable.v1.tar+zstd" => {
+                LayerMediaType::NondistributableTarZstd
+            }
             other => LayerMediaType::Other(other.into()),
         })
     }
@@ -158,6 +342,10 @@ impl std::fmt::Display for LayerMediaType {
                 LayerMediaType::NondistributableTarGz => {
                     "application/vnd.oci.image.layer.nondistributable.v1.tar+gzip"
                 }
+                LayerMediaType::TarZstd => "application/vnd.oci.image.layer.v1.tar+zstd",
+                LayerMediaType::NondistributableTarZstd => {
+                    "application/vnd.oci.image.layer.nondistributable.v1.tar+zstd"
+                }
                 // Assume other media types are gzipped.
                 LayerMediaType::Other(media_type) => media_type,
             }
@@ -186,20 +374,104 @@ impl Serialize for LayerMediaType {
 }
 /// Enum of Manifest structs for each schema version.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ManifestV2 {
     Schema1(ManifestV2_1),
     Schema2(ManifestV2_2),
     Schema2List(ManifestListV2_2),
+    Schema2OciIndex(ImageIndex),
 }
 
 impl ManifestV2 {
+    /// Iterate over the layers of an image manifest.
+    ///
+    /// Manifest lists and image indexes do not have layers of their own. They
+    /// must first be resolved to a single-platform manifest, e.g. using
+    /// [ManifestListV2_2::get_manifest_for_platform], otherwise
+    /// [ManifestError::NotAnImageManifest] is returned.
     pub fn layers(&self) -> Result<Box<dyn Iterator<Item = &dyn Layer> + '_>, RegistryError> {
         Ok(match self {
             ManifestV2::Schema1(s1) => Box::new(s1.layers.iter().map(|l| l as &dyn Layer)),
             ManifestV2::Schema2(s2) => Box::new(s2.layers.iter().map(|l| l as &dyn Layer)),
-            ManifestV2::Schema2List(_) => unimplemented!(),
+            ManifestV2::Schema2List(_) | ManifestV2::Schema2OciIndex(_) => {
+                return Err(RegistryError::ManifestError(
+                    ManifestError::NotAnImageManifest(self.into()),
+                ))
+            }
         })
     }
+
+    /// Serialize the manifest to JSON.
+    pub fn to_json(&self) -> Result<String, ManifestError> {
+        serde_json::to_string(self).map_err(ManifestError::JsonError)
+    }
+
+    /// Serialize the manifest to indented JSON.
+    pub fn to_json_pretty(&self) -> Result<String, ManifestError> {
+        serde_json::to_string_pretty(self).map_err(ManifestError::JsonError)
+    }
+
+    /// Parse a manifest from a reader without first reading it into a string.
+    ///
+    /// The JSON document is parsed once, the schema is determined from its
+    /// `schemaVersion` and `mediaType` fields and the parsed document is then
+    /// converted into the matching manifest type.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, ManifestError> {
+        let value: serde_json::Value =
+            serde_json::from_reader(reader).map_err(ManifestError::JsonError)?;
+
+        Self::try_from(value)
+    }
+}
+
+impl TryFrom<&str> for ManifestV2 {
+    type Error = ManifestError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for ManifestV2 {
+    type Error = ManifestError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Convert an already parsed JSON document, e.g. a manifest embedded in a
+/// larger document.
+impl TryFrom<serde_json::Value> for ManifestV2 {
+    type Error = ManifestError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        match probe_manifest_v2_schema_value(&value)? {
+            ManifestV2Schema::Schema1 => serde_json::from_value(value).map(ManifestV2::Schema1),
+            ManifestV2Schema::Schema2 => serde_json::from_value(value).map(ManifestV2::Schema2),
+            ManifestV2Schema::Schema2List => {
+                serde_json::from_value(value).map(ManifestV2::Schema2List)
+            }
+            ManifestV2Schema::Schema2OciIndex => {
+                serde_json::from_value(value).map(ManifestV2::Schema2OciIndex)
+            }
+        }
+        .map_err(ManifestError::JsonError)
+    }
+}
+
+impl Serialize for ManifestV2 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ManifestV2::Schema1(m) => m.serialize(serializer),
+            ManifestV2::Schema2(m) => m.serialize(serializer),
+            ManifestV2::Schema2List(m) => m.serialize(serializer),
+            ManifestV2::Schema2OciIndex(m) => m.serialize(serializer),
+        }
+    }
 }
 
 impl FromStr for ManifestV2 {
@@ -210,17 +482,66 @@ impl FromStr for ManifestV2 {
             ManifestV2Schema::Schema1 => serde_json::from_str(s).map(ManifestV2::Schema1),
             ManifestV2Schema::Schema2 => serde_json::from_str(s).map(ManifestV2::Schema2),
             ManifestV2Schema::Schema2List => serde_json::from_str(s).map(ManifestV2::Schema2List),
+            ManifestV2Schema::Schema2OciIndex => {
+                serde_json::from_str(s).map(ManifestV2::Schema2OciIndex)
+            }
         }
         .map_err(ManifestError::JsonError)
     }
 }
 
+impl TryFrom<ManifestV2> for ManifestV2_1 {
+    type Error = ManifestError;
+
+    fn try_from(manifest: ManifestV2) -> Result<Self, Self::Error> {
+        match manifest {
+            ManifestV2::Schema1(m) => Ok(m),
+            other => Err(ManifestError::UnexpectedSchema(other.into())),
+        }
+    }
+}
+
+impl TryFrom<ManifestV2> for ManifestV2_2 {
+    type Error = ManifestError;
+
+    fn try_from(manifest: ManifestV2) -> Result<Self, Self::Error> {
+        match manifest {
+            ManifestV2::Schema2(m) => Ok(m),
+            other => Err(ManifestError::UnexpectedSchema(other.into())),
+        }
+    }
+}
+
+impl TryFrom<ManifestV2> for ManifestListV2_2 {
+    type Error = ManifestError;
+
+    fn try_from(manifest: ManifestV2) -> Result<Self, Self::Error> {
+        match manifest {
+            ManifestV2::Schema2List(m) => Ok(m),
+            other => Err(ManifestError::UnexpectedSchema(other.into())),
+        }
+    }
+}
+
+impl TryFrom<ManifestV2> for ImageIndex {
+    type Error = ManifestError;
+
+    fn try_from(manifest: ManifestV2) -> Result<Self, Self::Error> {
+        match manifest {
+            ManifestV2::Schema2OciIndex(m) => Ok(m),
+            other => Err(ManifestError::UnexpectedSchema(other.into())),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 /// Discriminants for ManifestV2
+#[non_exhaustive]
 pub enum ManifestV2Schema {
     Schema1,
     Schema2,
     Schema2List,
+    Schema2OciIndex,
 }
 
 impl From<ManifestV2> for ManifestV2Schema {
@@ -229,6 +550,7 @@ impl From<ManifestV2> for ManifestV2Schema {
             ManifestV2::Schema1(_) => ManifestV2Schema::Schema1,
             ManifestV2::Schema2(_) => ManifestV2Schema::Schema2,
             ManifestV2::Schema2List(_) => ManifestV2Schema::Schema2List,
+            ManifestV2::Schema2OciIndex(_) => ManifestV2Schema::Schema2OciIndex,
         }
     }
 }
@@ -239,6 +561,7 @@ impl From<&ManifestV2> for ManifestV2Schema {
             ManifestV2::Schema1(_) => ManifestV2Schema::Schema1,
             ManifestV2::Schema2(_) => ManifestV2Schema::Schema2,
             ManifestV2::Schema2List(_) => ManifestV2Schema::Schema2List,
+            ManifestV2::Schema2OciIndex(_) => ManifestV2Schema::Schema2OciIndex,
         }
     }
 }
@@ -256,8 +579,28 @@ pub fn probe_manifest_v2_schema(data: &str) -> Result<ManifestV2Schema, Manifest
     let manifest: ManifestMediaTypeOnlyV2_2 =
         serde_json::from_str(data).map_err(ManifestError::JsonError)?;
 
-    let media_type = manifest.media_type();
+    probe_manifest_v2_2_media_type(manifest.media_type())
+}
+
+/// Determine the schema of an already parsed JSON manifest.
+fn probe_manifest_v2_schema_value(
+    value: &serde_json::Value,
+) -> Result<ManifestV2Schema, ManifestError> {
+    let manifest = ManifestSchemaOnlyV2::deserialize(value).map_err(ManifestError::JsonError)?;
+
+    match manifest.schema() {
+        1 => return Ok(ManifestV2Schema::Schema1),
+        2 => {}
+        schema => return Err(ManifestError::InvalidSchemaVersion(schema)),
+    };
+
+    let manifest =
+        ManifestMediaTypeOnlyV2_2::deserialize(value).map_err(ManifestError::JsonError)?;
 
+    probe_manifest_v2_2_media_type(manifest.media_type())
+}
+
+fn probe_manifest_v2_2_media_type(media_type: &str) -> Result<ManifestV2Schema, ManifestError> {
     #[allow(clippy::or_fun_call)]
     let media_type_split = media_type
         .split('+')
@@ -270,6 +613,8 @@ pub fn probe_manifest_v2_schema(data: &str) -> Result<ManifestV2Schema, Manifest
         // Docker seems to be compatible to OCI, so we also support those.
         "application/vnd.docker.distribution.manifest.v2" => Ok(ManifestV2Schema::Schema2),
         "application/vnd.docker.distribution.manifest.list.v2" => Ok(ManifestV2Schema::Schema2List),
+        "application/vnd.oci.image.manifest.v1" => Ok(ManifestV2Schema::Schema2),
+        "application/vnd.oci.image.index.v1" => Ok(ManifestV2Schema::Schema2OciIndex),
         _ => Err(ManifestError::InvalidMediaType(media_type.into())),
     }
 }
@@ -301,6 +646,223 @@ pub struct Digest {
     pub hex: String,
 }
 
+impl Digest {
+    /// Compute the digest of a buffer using the given algorithm.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    /// let digest = Digest::compute(DigestAlgorithm::Sha256, b"");
+    /// assert_eq!(&digest.to_string(), "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+    /// ```
+    pub fn compute(algorithm: DigestAlgorithm, data: &[u8]) -> Self {
+        let mut hasher = algorithm.new_hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// Create a digest from an algorithm and a hex-encoded hash.
+    ///
+    /// `hex` must be lowercase and have the length of hashes produced by
+    /// `algorithm`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    /// let hex = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    /// let digest = Digest::from_hex(DigestAlgorithm::Sha256, hex).expect("Invalid hex");
+    /// assert_eq!(digest, Digest::compute(DigestAlgorithm::Sha256, b""));
+    ///
+    /// assert!(Digest::from_hex(DigestAlgorithm::Sha512, hex).is_err());
+    /// ```
+    pub fn from_hex(algorithm: DigestAlgorithm, hex: &str) -> Result<Self, ManifestError> {
+        let valid = hex.len() == algorithm.hash_length()
+            && hex
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+
+        if !valid {
+            return Err(ManifestError::InvalidDigestHex(algorithm, hex.into()));
+        }
+
+        Ok(Digest {
+            algorithm,
+            hex: hex.into(),
+        })
+    }
+
+    /// Compute the digest of a buffer using the given algorithm.
+    ///
+    /// This is an alias of [Digest::compute], mirroring [Digest::from_reader].
+    pub fn from_bytes(algorithm: DigestAlgorithm, data: &[u8]) -> Self {
+        Self::compute(algorithm, data)
+    }
+
+    /// Compute the digest of a stream using the given algorithm.
+    ///
+    /// The content is hashed while it is being read, so it is never held in
+    /// memory as a whole.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    /// let digest = Digest::from_reader(DigestAlgorithm::Sha256, &b"hello world"[..])
+    ///     .expect("Could not read content");
+    /// assert_eq!(digest, Digest::from_bytes(DigestAlgorithm::Sha256, b"hello world"));
+    /// ```
+    pub fn from_reader<R: std::io::Read>(
+        algorithm: DigestAlgorithm,
+        mut reader: R,
+    ) -> Result<Self, std::io::Error> {
+        let mut hasher = algorithm.new_hasher();
+        std::io::copy(&mut reader, &mut hasher)?;
+        Ok(hasher.finalize())
+    }
+
+    /// Compute the BLAKE3 digest of a buffer.
+    #[cfg(feature = "blake3")]
+    pub fn from_bytes_blake3(data: &[u8]) -> Self {
+        Self::compute(DigestAlgorithm::Blake3, data)
+    }
+
+    /// Return true if `data` hashes to this digest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    /// let digest = Digest::compute(DigestAlgorithm::Sha512, b"hello");
+    /// assert!(digest.verify_bytes(b"hello"));
+    /// assert!(!digest.verify_bytes(b"world"));
+    /// ```
+    pub fn verify_bytes(&self, data: &[u8]) -> bool {
+        &Digest::compute(self.algorithm, data) == self
+    }
+
+    /// Wrap a reader to verify its content against this digest while it is
+    /// being read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    /// use std::io::Read;
+    ///
+    /// let digest = Digest::compute(DigestAlgorithm::Sha256, b"hello");
+    /// let mut reader = digest.verify_reader(&b"hello"[..]).unwrap();
+    ///
+    /// let mut content = String::new();
+    /// reader.read_to_string(&mut content).unwrap();
+    /// reader.finish().unwrap();
+    /// ```
+    pub fn verify_reader<R: std::io::Read>(
+        &self,
+        reader: R,
+    ) -> Result<VerifiedReader<R>, DigestMismatchError> {
+        Ok(VerifiedReader {
+            inner: reader,
+            hasher: self.algorithm.new_hasher(),
+            expected: self.clone(),
+        })
+    }
+
+    /// Return a digest consisting only of zeros, to be used as a sentinel
+    /// value.
+    ///
+    /// The zero digest does not describe any content. It must never be used
+    /// to verify content.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::{Digest, DigestAlgorithm};
+    /// let digest = Digest::zero(DigestAlgorithm::Sha256);
+    /// assert!(digest.is_zero());
+    /// ```
+    pub fn zero(algorithm: DigestAlgorithm) -> Self {
+        Digest {
+            algorithm,
+            hex: "0".repeat(algorithm.hash_length()),
+        }
+    }
+
+    /// Return the number of hex characters a digest of this digest's algorithm
+    /// has, i.e. twice [DigestAlgorithm::byte_length].
+    pub fn expected_hex_length(&self) -> usize {
+        self.algorithm.byte_length() * 2
+    }
+
+    /// Return true if this is the zero digest of its algorithm.
+    pub fn is_zero(&self) -> bool {
+        self.hex.len() == self.algorithm.hash_length() && self.hex.bytes().all(|b| b == b'0')
+    }
+
+    /// Return the first `n` characters of the hex portion.
+    ///
+    /// If `n` exceeds the length of the hex portion, the full hex portion is
+    /// returned.
+    pub fn truncated_hex(&self, n: usize) -> String {
+        self.hex[..n.min(self.hex.len())].to_owned()
+    }
+
+    /// Return the first 12 characters of the hex portion, as shown by tools
+    /// like `docker images`.
+    pub fn short_form(&self) -> String {
+        self.truncated_hex(12)
+    }
+
+    /// Return a displayable short form of the digest, consisting of the
+    /// algorithm and the first 12 characters of the hex portion.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::Digest;
+    /// let digest: Digest = "sha256:6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b".parse()
+    ///     .expect("parsing digest failed!");
+    /// assert_eq!(&digest.short_display().to_string(), "sha256:6c3c624b58db")
+    /// ```
+    pub fn short_display(&self) -> impl std::fmt::Display + '_ {
+        ShortDigest(self)
+    }
+
+    /// Parse a digest, accepting uppercase characters in the hex portion.
+    ///
+    /// The spec only allows lowercase hex, which is what `FromStr` enforces.
+    /// Some older tools emit uppercase hex however, so this lowercases the hex
+    /// portion before parsing. The resulting digest is always in its canonical
+    /// lowercase form.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///# use opencontainers::image::manifest::Digest;
+    /// let digest = Digest::parse_normalized("sha256:6C3C624B58DBBCD3C0DD82B4C53F04194D1247C6EEBDAAB7C610CF7D66709B3B")
+    ///     .expect("parsing digest failed!");
+    /// assert_eq!(&digest.to_string(), "sha256:6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b")
+    /// ```
+    pub fn parse_normalized(s: &str) -> Result<Self, ManifestError> {
+        match s.find(':') {
+            Some(index) => {
+                let (algorithm, hex) = s.split_at(index);
+                format!("{}{}", algorithm, hex.to_ascii_lowercase()).parse()
+            }
+            None => s.parse(),
+        }
+    }
+}
+
+struct ShortDigest<'a>(&'a Digest);
+
+impl<'a> std::fmt::Display for ShortDigest<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.0.algorithm, self.0.short_form())
+    }
+}
+
 impl std::fmt::Display for Digest {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}:{}", self.algorithm, self.hex)
@@ -319,6 +881,10 @@ impl std::str::FromStr for Digest {
             .map(|t| t.as_str().to_owned());
         let algorithm: DigestAlgorithm = digest.next().unwrap().parse()?;
         let hex = digest.next().unwrap();
+        if hex.len() != algorithm.hash_length() {
+            return Err(ManifestError::InvalidDigestHex(algorithm, hex));
+        }
+
         Ok(Self { algorithm, hex })
     }
 }
@@ -344,14 +910,157 @@ impl Serialize for Digest {
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[non_exhaustive]
 pub enum DigestAlgorithm {
     Sha256,
+    Sha384,
+    Sha512,
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    /// Return a streaming hasher for this algorithm.
+    ///
+    /// The hasher implements [std::io::Write], so content can be hashed while
+    /// it is being copied, e.g. using [std::io::copy].
+    pub fn new_hasher(self) -> Hasher {
+        let state = match self {
+            DigestAlgorithm::Sha256 => HasherState::Sha256(sha2::Sha256::new()),
+            DigestAlgorithm::Sha384 => HasherState::Sha384(sha2::Sha384::new()),
+            DigestAlgorithm::Sha512 => HasherState::Sha512(sha2::Sha512::new()),
+            #[cfg(feature = "blake3")]
+            DigestAlgorithm::Blake3 => HasherState::Blake3(Box::new(blake3::Hasher::new())),
+        };
+
+        Hasher {
+            algorithm: self,
+            state,
+        }
+    }
+
+    /// Return the length in bytes of the hash produced by this algorithm.
+    pub fn byte_length(self) -> usize {
+        match self {
+            DigestAlgorithm::Sha256 => 32,
+            DigestAlgorithm::Sha384 => 48,
+            DigestAlgorithm::Sha512 => 64,
+            #[cfg(feature = "blake3")]
+            DigestAlgorithm::Blake3 => 32,
+        }
+    }
+
+    /// Return the length of the hex-encoded hash produced by this algorithm.
+    pub fn hash_length(self) -> usize {
+        self.byte_length() * 2
+    }
+}
+
+enum HasherState {
+    Sha256(sha2::Sha256),
+    Sha384(sha2::Sha384),
+    Sha512(sha2::Sha512),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// Streaming hasher producing a [Digest].
+///
+/// Use [DigestAlgorithm::new_hasher] to create one.
+pub struct Hasher {
+    algorithm: DigestAlgorithm,
+    state: HasherState,
+}
+
+impl Hasher {
+    /// Return the algorithm used by this hasher.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Feed data into the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        match self.state {
+            HasherState::Sha256(ref mut h) => h.input(data),
+            HasherState::Sha384(ref mut h) => h.input(data),
+            HasherState::Sha512(ref mut h) => h.input(data),
+            #[cfg(feature = "blake3")]
+            HasherState::Blake3(ref mut h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    /// Consume the hasher and return the digest of all data fed into it.
+    pub fn finalize(self) -> Digest {
+        let hash = match self.state {
+            HasherState::Sha256(h) => h.result().to_vec(),
+            HasherState::Sha384(h) => h.result().to_vec(),
+            HasherState::Sha512(h) => h.result().to_vec(),
+            #[cfg(feature = "blake3")]
+            HasherState::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        };
+
+        Digest {
+            algorithm: self.algorithm,
+            hex: hash.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Hasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Hasher {{ algorithm: {} }}", self.algorithm)
+    }
+}
+
+impl std::io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reader verifying the content of an inner reader against a digest.
+///
+/// Use [Digest::verify_reader] to create one. All data read is hashed on the
+/// fly, [VerifiedReader::finish] compares the result with the expected digest.
+pub struct VerifiedReader<R> {
+    inner: R,
+    hasher: Hasher,
+    expected: Digest,
+}
+
+impl<R> VerifiedReader<R> {
+    /// Compare the digest of all data read so far with the expected digest.
+    ///
+    /// This should only be called after the inner reader has been read to
+    /// the end.
+    pub fn finish(self) -> Result<(), DigestMismatchError> {
+        DigestMismatchError::compare(&self.expected, self.hasher.finalize(), "content")
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for VerifiedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
 }
 
 impl std::fmt::Display for DigestAlgorithm {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             DigestAlgorithm::Sha256 => write!(f, "sha256"),
+            DigestAlgorithm::Sha384 => write!(f, "sha384"),
+            DigestAlgorithm::Sha512 => write!(f, "sha512"),
+            #[cfg(feature = "blake3")]
+            DigestAlgorithm::Blake3 => write!(f, "blake3"),
         }
     }
 }
@@ -362,6 +1071,10 @@ impl std::str::FromStr for DigestAlgorithm {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "sha256" => Ok(DigestAlgorithm::Sha256),
+            "sha384" => Ok(DigestAlgorithm::Sha384),
+            "sha512" => Ok(DigestAlgorithm::Sha512),
+            #[cfg(feature = "blake3")]
+            "blake3" => Ok(DigestAlgorithm::Blake3),
             other => Err(ManifestError::InvalidDigestAlgorithm(other.into())),
         }
     }
@@ -393,6 +1106,12 @@ pub struct FsLayerV2_1 {
     inner: Digest,
 }
 
+impl std::fmt::Display for FsLayerV2_1 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "layer(digest={})", self.inner)
+    }
+}
+
 impl Layer for FsLayerV2_1 {
     fn digest(&self) -> &Digest {
         &self.inner
@@ -422,9 +1141,77 @@ pub struct ManifestV2_1 {
 
     #[serde(rename = "fsLayers")]
     layers: Vec<FsLayerV2_1>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<V1Compatibility>,
+
+    /// JWS signatures of signed manifests, kept as-is so that re-serializing
+    /// the manifest does not drop them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    signatures: Vec<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl ManifestV2_1 {
+    /// Serialize the manifest to JSON.
+    ///
+    /// The signatures of a signed manifest are retained, but they only remain
+    /// valid if the payload is byte-for-byte identical to the signed one, which
+    /// re-serializing does not guarantee.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Convert the manifest to a schema 2 manifest.
+    ///
+    /// Schema 1 lists layers from the top-most to the base layer, so the
+    /// layers are reversed. As schema 1 carries neither media types nor sizes
+    /// for layers, each layer is assumed to be a gzipped tarball
+    /// ([LayerMediaType::TarGz]) with a size of 0.
+    ///
+    /// # Limitations
+    ///
+    /// Schema 1 has no config blob. The config of the returned manifest is a
+    /// placeholder with a size of 0 and the zero digest (see [Digest::zero]),
+    /// which must not be fetched. Converted manifests are therefore compared
+    /// by their layers rather than their config. Layer sizes are unknown and
+    /// must not be used to validate downloaded content. The digest of the
+    /// returned manifest differs from the digest of the original manifest.
+    pub fn to_v2_2(&self) -> ManifestV2_2 {
+        let config = ConfigV2_2 {
+            media_type: "application/vnd.docker.container.image.v1+json".into(),
+            size: 0,
+            digest: Digest::zero(DigestAlgorithm::Sha256),
+        };
+
+        let layers = self
+            .layers
+            .iter()
+            .rev()
+            .map(|layer| LayerV2_2 {
+                media_type: LayerMediaType::TarGz,
+                size: 0,
+                digest: layer.inner.clone(),
+                urls: None,
+                annotations: None,
+            })
+            .collect();
+
+        ManifestV2_2::for_docker(config, layers)
+    }
+}
+
+impl std::fmt::Display for ManifestV2_1 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "manifest(schema={}, layers={})",
+            self.schema,
+            self.layers.len()
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct ConfigV2_2 {
     /// The MIME type of the referenced object. This should generally be
     /// `application/vnd.docker.container.image.v1+json`.
@@ -444,9 +1231,61 @@ pub struct ConfigV2_2 {
 }
 
 impl ConfigV2_2 {
+    /// Create an OCI config descriptor for a config JSON blob.
+    ///
+    /// The digest is the SHA-256 digest of `data` and the media type is set to
+    /// `application/vnd.oci.image.config.v1+json`.
+    pub fn for_json_blob(data: &[u8]) -> Self {
+        ConfigV2_2 {
+            media_type: "application/vnd.oci.image.config.v1+json".into(),
+            size: data.len(),
+            digest: Digest::compute(DigestAlgorithm::Sha256, data),
+        }
+    }
+
+    /// Create a Docker config descriptor for a config JSON blob.
+    ///
+    /// The digest is the SHA-256 digest of `data` and the media type is set to
+    /// `application/vnd.docker.container.image.v1+json`.
+    pub fn for_docker_json_blob(data: &[u8]) -> Self {
+        ConfigV2_2 {
+            media_type: "application/vnd.docker.container.image.v1+json".into(),
+            ..Self::for_json_blob(data)
+        }
+    }
+
     pub fn digest(&self) -> &Digest {
         &self.digest
     }
+
+    /// Return the size of the config blob in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Fetch the config blob referenced by this descriptor from the registry
+    /// of `image`.
+    ///
+    /// The digest of the blob is verified before it is deserialized.
+    pub fn fetch_config(&self, image: &Image) -> Result<ImageV1, RegistryError> {
+        let mut blob = Vec::with_capacity(self.size);
+        image
+            .registry
+            .fetch_blob(&image.name, &self.digest)?
+            .read_to_end(&mut blob)
+            .map_err(RegistryError::IoError)?;
+
+        DigestMismatchError::check(&self.digest, &blob, "config")?;
+
+        serde_json::from_slice(&blob)
+            .map_err(|e| RegistryError::ImageSpecError(ImageSpecError::JsonError(e)))
+    }
+}
+
+impl std::fmt::Display for ConfigV2_2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "config(digest={}, size={}B)", self.digest, self.size)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -476,6 +1315,64 @@ pub struct LayerV2_2 {
     /// Content should be verified against the digest and size. This field is
     /// optional and uncommon.
     urls: Option<Vec<String>>,
+
+    /// Arbitrary metadata for the layer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    annotations: Option<HashMap<String, String>>,
+}
+
+impl LayerV2_2 {
+    /// Create a layer descriptor for a blob held in memory.
+    ///
+    /// The digest is the SHA-256 digest of `data`. The descriptor has no URLs.
+    ///
+    /// Empty blobs are rejected, as empty layers are referenced by a
+    /// well-known digest instead of being described from their content.
+    pub fn for_blob(media_type: LayerMediaType, data: &[u8]) -> Result<Self, ManifestBuildError> {
+        if data.is_empty() {
+            return Err(ManifestBuildError::EmptyLayer);
+        }
+
+        Ok(LayerV2_2 {
+            media_type,
+            size: data.len(),
+            digest: Digest::compute(DigestAlgorithm::Sha256, data),
+            urls: None,
+            annotations: None,
+        })
+    }
+
+    /// Return the size of the layer blob in bytes.
+    ///
+    /// Unlike [Layer::size], the size is always known for schema 2 layers.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return the URLs the layer blob may be fetched from, if any.
+    ///
+    /// These are typically set for foreign layers, which are not pushed to
+    /// registries.
+    pub fn urls(&self) -> Option<&[String]> {
+        self.urls.as_deref()
+    }
+
+    /// Return the annotations of the layer, if any.
+    pub fn annotations(&self) -> Option<&HashMap<String, String>> {
+        self.annotations.as_ref()
+    }
+}
+
+impl std::fmt::Display for LayerV2_2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "layer(digest={}, size={}B, type={})",
+            self.digest,
+            self.size,
+            self.media_type.short_name()
+        )
+    }
 }
 
 impl Layer for LayerV2_2 {
@@ -486,10 +1383,46 @@ impl Layer for LayerV2_2 {
     fn media_type(&self) -> Option<&LayerMediaType> {
         Some(&self.media_type)
     }
+
+    fn size(&self) -> Option<usize> {
+        Some(LayerV2_2::size(self))
+    }
+
+    fn urls(&self) -> Option<&[String]> {
+        LayerV2_2::urls(self)
+    }
+}
+
+/// A reference to targeted content.
+///
+/// # Spec
+///
+/// > A descriptor describes the disposition of targeted content. It includes
+/// > the type of the content, a content identifier (digest), and the byte-size
+/// > of the raw content.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Descriptor {
+    /// The media type of the referenced content.
+    #[serde(rename = "mediaType")]
+    pub media_type: String,
+
+    /// The size in bytes of the referenced content.
+    pub size: usize,
+
+    /// The digest of the referenced content.
+    pub digest: Digest,
+
+    /// A list of URIs from which the content may be downloaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urls: Option<Vec<String>>,
+
+    /// Arbitrary metadata for the descriptor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
 }
 
 /// Image Manifest Version 2, Schema 2
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ManifestV2_2 {
     /// This field specifies the image manifest schema version as an integer.
     ///
@@ -515,9 +1448,384 @@ pub struct ManifestV2_2 {
     ///
     /// (opposite order of schema1).
     pub layers: Vec<LayerV2_2>,
+
+    /// Arbitrary metadata for the image manifest. This property MUST use the
+    /// [annotation rules].
+    ///
+    /// [annotation rules]: https://github.com/opencontainers/image-spec/blob/master/annotations.md#rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+
+    /// A descriptor of another manifest this manifest refers to, e.g. the
+    /// image a signature or attestation manifest applies to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<Descriptor>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl ManifestV2_2 {
+    /// Create an image manifest referencing the given config and layers.
+    ///
+    /// This is the same as [ManifestV2_2::for_docker], i.e. the media type is
+    /// set to `application/vnd.docker.distribution.manifest.v2+json`.
+    pub fn new(config: ConfigV2_2, layers: Vec<LayerV2_2>) -> Self {
+        Self::for_docker(config, layers)
+    }
+
+    /// Create an OCI image manifest referencing the given config and layers.
+    ///
+    /// The media type is set to `application/vnd.oci.image.manifest.v1+json`.
+    pub fn from_config_and_layers(config: ConfigV2_2, layers: Vec<LayerV2_2>) -> Self {
+        ManifestV2_2 {
+            schema: 2,
+            media_type: "application/vnd.oci.image.manifest.v1+json".into(),
+            config,
+            layers,
+            annotations: None,
+            subject: None,
+        }
+    }
+
+    /// Create a Docker image manifest referencing the given config and layers.
+    ///
+    /// The media type is set to
+    /// `application/vnd.docker.distribution.manifest.v2+json`.
+    pub fn for_docker(config: ConfigV2_2, layers: Vec<LayerV2_2>) -> Self {
+        ManifestV2_2 {
+            media_type: "application/vnd.docker.distribution.manifest.v2+json".into(),
+            ..Self::from_config_and_layers(config, layers)
+        }
+    }
+
+    /// Return the layers that can be fetched from the registry.
+    ///
+    /// Per the spec, non-distributable layers are typically not uploaded to
+    /// registries. Unless they carry `urls` to fetch them from, they are
+    /// excluded.
+    pub fn applicable_registry_layers(&self) -> impl Iterator<Item = &LayerV2_2> + '_ {
+        self.layers.iter().filter(|layer| {
+            layer.media_type.is_distributable()
+                || layer.urls.as_ref().map_or(false, |urls| !urls.is_empty())
+        })
+    }
+
+    /// Return whether all layers can be fetched from the registry.
+    ///
+    /// See [ManifestV2_2::applicable_registry_layers].
+    pub fn all_layers_fetchable_from_registry(&self) -> bool {
+        self.applicable_registry_layers().count() == self.layers.len()
+    }
+
+    /// Return the number of bytes to download to pull the image, i.e. the size
+    /// of the config blob and all layers.
+    ///
+    /// See [ConfigV2_2::size] and [LayerV2_2::size].
+    pub fn total_size(&self) -> u64 {
+        self.config.size() as u64 + self.compressed_layer_size()
+    }
+
+    /// Return the combined size of all layers as stored in the registry.
+    ///
+    /// For compressed layers, this is the compressed size. See
+    /// [LayerV2_2::size].
+    pub fn compressed_layer_size(&self) -> u64 {
+        self.layers.iter().map(|layer| layer.size() as u64).sum()
+    }
+
+    /// Return the layers of the manifest, skipping layers whose digest already
+    /// appeared earlier in the manifest.
+    ///
+    /// The order of first occurrence is preserved.
+    pub fn deduped_layers(&self) -> Vec<&LayerV2_2> {
+        let mut seen = HashSet::new();
+        self.layers
+            .iter()
+            .filter(|layer| seen.insert(&layer.digest))
+            .collect()
+    }
+
+    /// Return whether a layer digest appears more than once in the manifest.
+    pub fn has_duplicate_layers(&self) -> bool {
+        self.deduped_layers().len() != self.layers.len()
+    }
+
+    /// Verify that a config blob matches the config digest of the manifest.
+    pub fn verify_config_digest(&self, config_bytes: &[u8]) -> Result<(), DigestMismatchError> {
+        DigestMismatchError::check(&self.config.digest, config_bytes, "config")
+    }
+
+    /// Verify that a layer blob matches the digest of the layer at
+    /// `layer_index`.
+    ///
+    /// # Panics
+    /// Panics if `layer_index` is out of bounds.
+    pub fn verify_layer_digest(
+        &self,
+        layer_index: usize,
+        blob_bytes: &[u8],
+    ) -> Result<(), DigestMismatchError> {
+        DigestMismatchError::check(
+            &self.layers[layer_index].digest,
+            blob_bytes,
+            &format!("layer {}", layer_index),
+        )
+    }
+
+    /// Pair each layer with its DiffID from the image config.
+    ///
+    /// The image config lists the digests of the uncompressed layers (DiffIDs)
+    /// in the same order as the layers of the manifest. A differing count
+    /// means the manifest and config are inconsistent.
+    pub fn match_diff_ids<'a>(
+        &'a self,
+        diff_ids: &'a [Digest],
+    ) -> Result<Vec<(&'a LayerV2_2, &'a Digest)>, DiffIdError> {
+        if self.layers.len() != diff_ids.len() {
+            return Err(DiffIdError::LayerCountMismatch {
+                layers: self.layers.len(),
+                diff_ids: diff_ids.len(),
+            });
+        }
+
+        Ok(self.layers.iter().zip(diff_ids.iter()).collect())
+    }
+
+    /// Return whether this is a scratch image, consisting of a single empty
+    /// layer.
+    pub fn is_scratch(&self) -> bool {
+        self.layers.len() == 1 && self.layers[0].digest.to_string() == EMPTY_LAYER_DIGEST
+    }
+
+    /// Return the descriptor of the manifest this manifest refers to.
+    pub fn subject(&self) -> Option<&Descriptor> {
+        self.subject.as_ref()
+    }
+
+    /// Return whether this manifest refers to the manifest with the given
+    /// digest through its `subject` field.
+    pub fn is_attestation_for(&self, subject_digest: &Digest) -> bool {
+        self.subject.as_ref().map(|s| &s.digest) == Some(subject_digest)
+    }
+
+    /// Set the schema version of the manifest.
+    ///
+    /// Image manifests only exist in schema version 2, so any other version
+    /// is rejected.
+    pub fn with_schema_version(mut self, version: u64) -> Result<Self, ManifestBuildError> {
+        if version != 2 {
+            return Err(ManifestBuildError::UnsupportedSchemaVersion(version));
+        }
+
+        self.schema = version;
+        Ok(self)
+    }
+
+    /// Return the values of the [pre-defined annotation keys] of the manifest.
+    ///
+    /// [pre-defined annotation keys]: https://github.com/opencontainers/image-spec/blob/master/annotations.md#pre-defined-annotation-keys
+    pub fn oci_annotations(&self) -> OciAnnotations {
+        self.annotations
+            .as_ref()
+            .map(OciAnnotations::from_annotations)
+            .unwrap_or_default()
+    }
+
+    /// Return the annotations of the manifest, if any.
+    pub fn annotations(&self) -> Option<&HashMap<String, String>> {
+        self.annotations.as_ref()
+    }
+
+    /// Return the raw value of the `org.opencontainers.image.created`
+    /// annotation.
+    pub fn created_at_string(&self) -> Option<&str> {
+        self.annotations
+            .as_ref()?
+            .get("org.opencontainers.image.created")
+            .map(String::as_str)
+    }
+
+    /// Return the time the image was created at, as given by the
+    /// `org.opencontainers.image.created` annotation.
+    ///
+    /// Returns `None` if the annotation is absent or not a valid RFC 3339
+    /// timestamp.
+    pub fn created_at(&self) -> Option<std::time::SystemTime> {
+        chrono::DateTime::parse_from_rfc3339(self.created_at_string()?)
+            .ok()
+            .map(std::time::SystemTime::from)
+    }
+
+    /// Return a compact one-line summary of the manifest for logging.
+    ///
+    /// The summary contains the shortened config digest, the number of layers
+    /// and the combined size of all layers in bytes, e.g.
+    /// `Schema2[config=sha256:b5b2b2c507a0..., layers=3, size=122487]`.
+    pub fn to_schema_string(&self) -> String {
+        let size: usize = self.layers.iter().map(|l| l.size).sum();
+
+        format!(
+            "Schema2[config={}..., layers={}, size={}]",
+            self.config.digest.short_display(),
+            self.layers.len(),
+            size
+        )
+    }
+}
+
+/// The layers that differ between two image manifests.
+///
+/// See [image_diff].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImageDiff {
+    /// Layers of the new manifest that are not in the old one.
+    pub added: Vec<LayerV2_2>,
+
+    /// Layers of the old manifest that are not in the new one.
+    pub removed: Vec<LayerV2_2>,
+
+    /// Layers of the new manifest that are also in the old one.
+    pub unchanged: Vec<LayerV2_2>,
+}
+
+/// Compare the layers of two image manifests by digest.
+///
+/// Layers keep the order of the manifest they are taken from, i.e. `removed`
+/// is in the order of `old`, `added` and `unchanged` in the order of `new`.
+pub fn image_diff(old: &ManifestV2_2, new: &ManifestV2_2) -> ImageDiff {
+    let old_digests: HashSet<&Digest> = old.layers.iter().map(|l| &l.digest).collect();
+    let new_digests: HashSet<&Digest> = new.layers.iter().map(|l| &l.digest).collect();
+
+    let (unchanged, added): (Vec<_>, Vec<_>) = new
+        .layers
+        .iter()
+        .cloned()
+        .partition(|l| old_digests.contains(&l.digest));
+    let removed = old
+        .layers
+        .iter()
+        .filter(|l| !new_digests.contains(&l.digest))
+        .cloned()
+        .collect();
+
+    ImageDiff {
+        added,
+        removed,
+        unchanged,
+    }
+}
+
+impl std::fmt::Display for ManifestV2_2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_schema_string())
+    }
+}
+
+/// Manifests are identified by their config digest, which is the image ID in
+/// Docker's object model. Manifests differing only in e.g. annotations or
+/// layer media types are considered equal.
+///
+/// Manifests converted from schema 1 (see [ManifestV2_1::to_v2_2]) all share
+/// the zero digest as a placeholder config, so they are identified by their
+/// layer digests instead.
+impl PartialEq for ManifestV2_2 {
+    fn eq(&self, other: &Self) -> bool {
+        if self.config.digest != other.config.digest {
+            return false;
+        }
+
+        !self.config.digest.is_zero()
+            || self
+                .layers
+                .iter()
+                .map(|l| &l.digest)
+                .eq(other.layers.iter().map(|l| &l.digest))
+    }
+}
+
+impl Eq for ManifestV2_2 {}
+
+impl std::hash::Hash for ManifestV2_2 {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::hash::Hash::hash(&self.config.digest, state);
+
+        if self.config.digest.is_zero() {
+            for layer in &self.layers {
+                std::hash::Hash::hash(&layer.digest, state);
+            }
+        }
+    }
+}
+
+/// Values of the pre-defined annotation keys of the OCI image spec.
+///
+/// Each field corresponds to an `org.opencontainers.image.*` annotation and is
+/// `None` if the annotation is absent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OciAnnotations {
+    /// `org.opencontainers.image.title`: Human-readable title of the image.
+    pub title: Option<String>,
+
+    /// `org.opencontainers.image.description`: Human-readable description of
+    /// the software packaged in the image.
+    pub description: Option<String>,
+
+    /// `org.opencontainers.image.url`: URL to find more information on the
+    /// image.
+    pub url: Option<String>,
+
+    /// `org.opencontainers.image.source`: URL to get source code for building
+    /// the image.
+    pub source: Option<String>,
+
+    /// `org.opencontainers.image.version`: Version of the packaged software.
+    pub version: Option<String>,
+
+    /// `org.opencontainers.image.revision`: Source control revision
+    /// identifier for the packaged software.
+    pub revision: Option<String>,
+
+    /// `org.opencontainers.image.vendor`: Name of the distributing entity,
+    /// organization or individual.
+    pub vendor: Option<String>,
+
+    /// `org.opencontainers.image.licenses`: License(s) under which contained
+    /// software is distributed as an SPDX License Expression.
+    pub licenses: Option<String>,
+
+    /// `org.opencontainers.image.ref.name`: Name of the reference for a
+    /// target.
+    pub ref_name: Option<String>,
+
+    /// `org.opencontainers.image.created`: Date and time on which the image
+    /// was built (string, date-time as defined by RFC 3339).
+    pub created: Option<String>,
+
+    /// `org.opencontainers.image.authors`: Contact details of the people or
+    /// organization responsible for the image (freeform string).
+    pub authors: Option<String>,
+}
+
+impl OciAnnotations {
+    /// Extract the pre-defined annotation values from an annotation map.
+    pub fn from_annotations(annotations: &HashMap<String, String>) -> Self {
+        let get = |key: &str| annotations.get(key).cloned();
+
+        OciAnnotations {
+            title: get("org.opencontainers.image.title"),
+            description: get("org.opencontainers.image.description"),
+            url: get("org.opencontainers.image.url"),
+            source: get("org.opencontainers.image.source"),
+            version: get("org.opencontainers.image.version"),
+            revision: get("org.opencontainers.image.revision"),
+            vendor: get("org.opencontainers.image.vendor"),
+            licenses: get("org.opencontainers.image.licenses"),
+            ref_name: get("org.opencontainers.image.ref.name"),
+            created: get("org.opencontainers.image.created"),
+            authors: get("org.opencontainers.image.authors"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct ManifestPlatformV2_2 {
     /// The architecture field specifies the CPU architecture, for example
     /// amd64 or ppc64le.
@@ -547,6 +1855,69 @@ pub struct ManifestPlatformV2_2 {
 }
 
 impl ManifestPlatformV2_2 {
+    /// Return the platform in the format of Docker's `--platform` flag, i.e.
+    /// `os/architecture` or `os/architecture/variant`.
+    ///
+    /// The variant is only included if it is set and non-empty.
+    pub fn to_platform_spec_string(&self) -> String {
+        match self.variant {
+            Some(ref variant) if !variant.is_empty() => {
+                format!("{}/{}/{}", self.os, self.architecture, variant)
+            }
+            _ => format!("{}/{}", self.os, self.architecture),
+        }
+    }
+
+    /// Parse a platform in the format of Docker's `--platform` flag, i.e.
+    /// `os/architecture` or `os/architecture/variant`.
+    pub fn from_platform_spec_string(s: &str) -> Result<Self, PlatformParseError> {
+        let parts: Vec<&str> = s.split('/').collect();
+
+        let (os, architecture, variant) = match parts.as_slice() {
+            [os, architecture] => (os, architecture, None),
+            [os, architecture, variant] if !variant.is_empty() => {
+                (os, architecture, Some(variant.to_string()))
+            }
+            _ => return Err(PlatformParseError::InvalidFormat(s.into())),
+        };
+
+        Ok(ManifestPlatformV2_2 {
+            architecture: architecture
+                .parse()
+                .map_err(|_| PlatformParseError::InvalidArch(architecture.to_string()))?,
+            os: os
+                .parse()
+                .map_err(|_| PlatformParseError::InvalidOs(os.to_string()))?,
+            osversion: None,
+            osfeatures: None,
+            variant,
+            features: None,
+        })
+    }
+
+    /// Return the platform as a slash-separated string like `linux/amd64`.
+    #[deprecated(note = "use `to_platform_spec_string` instead")]
+    pub fn to_platform_string(&self) -> String {
+        self.to_platform_spec_string()
+    }
+
+    /// Parse a slash-separated platform string like `linux/arm/v7`.
+    #[deprecated(note = "use `from_platform_spec_string` instead")]
+    pub fn from_platform_string(s: &str) -> Result<Self, PlatformParseError> {
+        Self::from_platform_spec_string(s)
+    }
+
+    /// Return whether the platform matches an explicitly given architecture
+    /// and operating system.
+    ///
+    /// `arch` and `os` are parsed as `GOARCH` and `GOOS` values respectively,
+    /// so e.g. both `x86_64` and `amd64` are accepted. Values that cannot be
+    /// parsed never match.
+    pub fn matches_platform(&self, arch: &str, os: &str) -> bool {
+        arch.parse::<go::GoArch>().ok() == Some(self.architecture)
+            && os.parse::<go::GoOs>().ok() == Some(self.os)
+    }
+
     pub fn current_platform_matches(&self) -> bool {
         self.current_arch_matches()
             && self.current_os_matches()
@@ -555,8 +1926,7 @@ impl ManifestPlatformV2_2 {
     }
 
     pub fn current_arch_matches(&self) -> bool {
-        let current_arch = std::env::consts::ARCH.parse::<go::GoArch>().ok();
-        current_arch == Some(self.architecture)
+        go::GoArch::current() == Some(self.architecture)
     }
 
     pub fn current_os_matches(&self) -> bool {
@@ -581,6 +1951,50 @@ impl ManifestPlatformV2_2 {
         // FIXME: on arm, we should really check the arm variant here.
         true
     }
+
+    /// Return the CPU architecture.
+    pub fn architecture(&self) -> go::GoArch {
+        self.architecture
+    }
+
+    /// Return the operating system.
+    pub fn os(&self) -> go::GoOs {
+        self.os
+    }
+
+    /// Return the operating system version, e.g. `10.0.10586`, if set.
+    pub fn os_version(&self) -> Option<&str> {
+        self.osversion.as_deref()
+    }
+
+    /// Return the required operating system features, if set.
+    pub fn os_features(&self) -> Option<&[String]> {
+        self.osfeatures.as_deref()
+    }
+
+    /// Return the CPU variant, e.g. `v7`, if set.
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+
+    /// Return the required CPU features, if set.
+    pub fn features(&self) -> Option<&[String]> {
+        self.features.as_deref()
+    }
+}
+
+impl FromStr for ManifestPlatformV2_2 {
+    type Err = PlatformParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_platform_spec_string(s)
+    }
+}
+
+impl std::fmt::Display for ManifestPlatformV2_2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_platform_spec_string())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -610,6 +2024,38 @@ pub struct ManifestListEntryV2_2 {
     pub platform: ManifestPlatformV2_2,
 }
 
+impl ManifestListEntryV2_2 {
+    /// Return the media type of the referenced manifest.
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    /// Return the size of the referenced manifest in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return the digest of the referenced manifest.
+    pub fn digest(&self) -> &Digest {
+        &self.digest
+    }
+
+    /// Return the platform the referenced manifest runs on.
+    pub fn platform(&self) -> &ManifestPlatformV2_2 {
+        &self.platform
+    }
+}
+
+impl std::fmt::Display for ManifestListEntryV2_2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "manifest(digest={}, size={}B, platform={})",
+            self.digest, self.size, self.platform
+        )
+    }
+}
+
 /// Manifest List
 ///
 /// The manifest list is the “fat manifest” which points to specific image
@@ -636,6 +2082,45 @@ pub struct ManifestListV2_2 {
 }
 
 impl ManifestListV2_2 {
+    /// Create a Docker manifest list from existing entries.
+    ///
+    /// The media type is set to
+    /// `application/vnd.docker.distribution.manifest.list.v2+json`.
+    pub fn new(manifests: Vec<ManifestListEntryV2_2>) -> Self {
+        ManifestListV2_2 {
+            schema: 2,
+            media_type: "application/vnd.docker.distribution.manifest.list.v2+json".into(),
+            manifests,
+        }
+    }
+
+    /// Create a Docker manifest list from image manifests and their
+    /// platforms.
+    ///
+    /// Each manifest is serialized to JSON and referenced by the SHA-256
+    /// digest and size of that serialization, so the manifests must be pushed
+    /// in exactly this serialization. Use [ImageIndex::from_image_manifests]
+    /// to create an OCI image index instead.
+    pub fn from_image_manifests(
+        entries: Vec<(ManifestV2_2, ManifestPlatformV2_2)>,
+    ) -> Result<Self, ManifestBuildError> {
+        let manifests = entries
+            .into_iter()
+            .map(|(manifest, platform)| {
+                let (size, digest) = manifest_size_and_digest(&manifest)?;
+
+                Ok(ManifestListEntryV2_2 {
+                    media_type: manifest.media_type,
+                    size,
+                    digest,
+                    platform,
+                })
+            })
+            .collect::<Result<Vec<_>, ManifestBuildError>>()?;
+
+        Ok(ManifestListV2_2::new(manifests))
+    }
+
     pub fn get_current_platform_manifest_digest<T>(&self) -> Option<&Digest>
     where
         T: ImageSelector,
@@ -643,6 +2128,152 @@ impl ManifestListV2_2 {
         T::select_manifest(self).map(|entry| &entry.digest)
     }
 
+    /// Return the manifests for the given operating system.
+    ///
+    /// `os` is parsed as a `GOOS` value, values that cannot be parsed match no
+    /// manifest.
+    pub fn filter_by_os(&self, os: &str) -> Vec<&ManifestListEntryV2_2> {
+        let os = os.parse::<go::GoOs>().ok();
+        self.manifests
+            .iter()
+            .filter(|m| Some(m.platform.os) == os)
+            .collect()
+    }
+
+    /// Return the manifests for the given architecture.
+    ///
+    /// `arch` is parsed as a `GOARCH` value, values that cannot be parsed
+    /// match no manifest.
+    pub fn filter_by_arch(&self, arch: &str) -> Vec<&ManifestListEntryV2_2> {
+        let arch = arch.parse::<go::GoArch>().ok();
+        self.manifests
+            .iter()
+            .filter(|m| Some(m.platform.architecture) == arch)
+            .collect()
+    }
+
+    /// Return the manifests for the given architecture and operating system.
+    ///
+    /// See [ManifestPlatformV2_2::matches_platform].
+    pub fn filter_by_platform(&self, arch: &str, os: &str) -> Vec<&ManifestListEntryV2_2> {
+        self.manifests
+            .iter()
+            .filter(|m| m.platform.matches_platform(arch, os))
+            .collect()
+    }
+
+    /// Return all architectures manifests are available for.
+    pub fn architectures(&self) -> HashSet<go::GoArch> {
+        self.manifests
+            .iter()
+            .map(|m| m.platform.architecture)
+            .collect()
+    }
+
+    /// Return all operating systems manifests are available for.
+    pub fn operating_systems(&self) -> HashSet<go::GoOs> {
+        self.manifests.iter().map(|m| m.platform.os).collect()
+    }
+
+    /// Return a compact one-line summary of the manifest list for logging.
+    ///
+    /// The summary lists the platforms of all manifests in the list, e.g.
+    /// `Schema2List[platforms=2: linux/amd64, linux/arm64]`.
+    pub fn to_schema_string(&self) -> String {
+        let platforms: Vec<String> = self
+            .manifests
+            .iter()
+            .map(|m| m.platform.to_platform_spec_string())
+            .collect();
+
+        format!(
+            "Schema2List[platforms={}: {}]",
+            platforms.len(),
+            platforms.join(", ")
+        )
+    }
+
+    /// Find the manifest entry with the given digest.
+    pub fn find_manifest_by_digest(&self, digest: &Digest) -> Option<&ManifestListEntryV2_2> {
+        self.manifests.iter().find(|m| &m.digest == digest)
+    }
+
+    /// Return whether the manifest list references a manifest with the given
+    /// digest.
+    pub fn contains_digest(&self, digest: &Digest) -> bool {
+        self.find_manifest_by_digest(digest).is_some()
+    }
+
+    /// Find all manifest entries for a given architecture and operating
+    /// system.
+    ///
+    /// A manifest list may contain several entries for the same platform, e.g.
+    /// for different CPU variants, so all matching entries are returned. This
+    /// is the same as [ManifestListV2_2::filter_by_platform].
+    pub fn find_manifests_for_platform(&self, arch: &str, os: &str) -> Vec<&ManifestListEntryV2_2> {
+        self.filter_by_platform(arch, os)
+    }
+
+    /// Return groups of manifest indices that share the same platform.
+    ///
+    /// Groups are ordered by the index of their first entry.
+    fn duplicate_platform_indices(&self) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = vec![];
+
+        for (index, entry) in self.manifests.iter().enumerate() {
+            match groups
+                .iter_mut()
+                .find(|group| self.manifests[group[0]].platform == entry.platform)
+            {
+                Some(group) => group.push(index),
+                None => groups.push(vec![index]),
+            }
+        }
+
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// Return whether any platform occurs more than once in the manifest list.
+    pub fn has_duplicate_platforms(&self) -> bool {
+        self.manifests.iter().enumerate().any(|(index, entry)| {
+            self.manifests[..index]
+                .iter()
+                .any(|other| other.platform == entry.platform)
+        })
+    }
+
+    /// Check that no platform occurs more than once in the manifest list.
+    ///
+    /// On failure, the error holds every duplicated platform together with the
+    /// indices of all entries for that platform, ordered by the index of
+    /// their first entry.
+    pub fn validate_no_duplicate_platforms(&self) -> Result<(), ManifestValidationError> {
+        let conflicts: Vec<_> = self
+            .duplicate_platform_indices()
+            .into_iter()
+            .map(|indices| (self.manifests[indices[0]].platform.clone(), indices))
+            .collect();
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(ManifestValidationError::DuplicatePlatforms(conflicts))
+        }
+    }
+
+    /// Validate the manifest list.
+    ///
+    /// A valid manifest list contains at least one manifest and no duplicate
+    /// platforms.
+    pub fn validate(&self) -> Result<(), ManifestValidationError> {
+        if self.manifests.is_empty() {
+            return Err(ManifestValidationError::EmptyManifestList);
+        }
+
+        self.validate_no_duplicate_platforms()
+    }
+
     /// Get a platform manifest for the current platform from a manifest list.
     pub fn get_current_platform_manifest<T>(
         &self,
@@ -656,23 +2287,224 @@ impl ManifestListV2_2 {
             .ok_or(ManifestError::NoMatchingPlatformFound)
             .map_err(RegistryError::ManifestError)?;
 
+        Self::get_platform_manifest(digest, image)
+    }
+
+    /// Get a platform manifest for the current platform from a manifest list,
+    /// using the [CurrentPlatformSelector].
+    ///
+    /// Rust does not allow default type parameters on functions, so this is
+    /// the non-generic shorthand for
+    /// `get_current_platform_manifest::<CurrentPlatformSelector>`.
+    pub fn get_default_platform_manifest(
+        &self,
+        image: &Image,
+    ) -> Result<ManifestV2_2, RegistryError> {
+        self.get_current_platform_manifest::<CurrentPlatformSelector>(image)
+    }
+
+    /// Get the platform manifest for an explicitly given architecture and
+    /// operating system from a manifest list.
+    ///
+    /// This allows selecting an image for a platform other than the current
+    /// one, e.g. an `arm64` image on an `x86_64` host.
+    pub fn get_manifest_for_platform(
+        &self,
+        arch: &str,
+        os: &str,
+        image: &Image,
+    ) -> Result<ManifestV2_2, RegistryError> {
+        let digest = self
+            .manifests
+            .iter()
+            .find(|m| m.platform.matches_platform(arch, os))
+            .map(|m| &m.digest)
+            .ok_or(ManifestError::NoMatchingPlatformFound)
+            .map_err(RegistryError::ManifestError)?;
+
+        Self::get_platform_manifest(digest, image)
+    }
+
+    /// Fetch a platform manifest referenced by this manifest list.
+    ///
+    /// The manifest media types of the registry are accepted, and the manifest
+    /// is verified against `digest` before it is deserialized.
+    fn get_platform_manifest(
+        digest: &Digest,
+        image: &Image,
+    ) -> Result<ManifestV2_2, RegistryError> {
         let url = format!(
             "{}/v2/{}/manifests/{}",
             image.registry.url, image.name, digest
         );
 
-        let blob = image
+        let headers = image.registry.manifest_accept_headers()?;
+        let mut blob = vec![];
+        image
             .registry
-            .get(&url, None)?
-            .text()
+            .get(&url, Some(&headers))?
+            .copy_to(&mut blob)
             .map_err(RegistryError::ReqwestError)?;
 
-        serde_json::from_str(&blob)
+        DigestMismatchError::check(digest, &blob, "manifest")?;
+
+        serde_json::from_slice(&blob)
             .map_err(ManifestError::JsonError)
             .map_err(RegistryError::ManifestError)
     }
 }
 
+impl std::fmt::Display for ManifestListV2_2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_schema_string())
+    }
+}
+
+/// Entry of an OCI image index.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ImageIndexEntry {
+    /// The media type of the referenced manifest or index.
+    #[serde(rename = "mediaType")]
+    pub media_type: String,
+
+    /// The size in bytes of the referenced content.
+    pub size: usize,
+
+    /// The digest of the referenced content.
+    pub digest: Digest,
+
+    /// The platform the referenced image runs on, if it is platform specific.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<ManifestPlatformV2_2>,
+
+    /// A list of URIs from which the content may be downloaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urls: Option<Vec<String>>,
+
+    /// Arbitrary metadata for the entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+}
+
+/// OCI Image Index
+///
+/// # Spec
+///
+/// > The image index is a higher-level manifest which points to specific image
+/// > manifests, ideal for one or more platforms. While the use of an image
+/// > index is OPTIONAL for image providers, image consumers SHOULD be prepared
+/// > to process them.
+///
+/// Unlike the manifest list, entries of an image index do not need to carry a
+/// platform, and the index as well as each entry may carry annotations.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ImageIndex {
+    /// This field specifies the image index schema version as an integer.
+    ///
+    /// This schema uses version 2.
+    #[serde(rename = "schemaVersion")]
+    pub schema: u64,
+
+    /// The media type of the index. This should be set to
+    /// `application/vnd.oci.image.index.v1+json`.
+    #[serde(rename = "mediaType", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+
+    /// The manifests referenced by the index.
+    pub manifests: Vec<ImageIndexEntry>,
+
+    /// Arbitrary metadata for the image index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+}
+
+impl ImageIndex {
+    /// Create an OCI image index from image manifests and their platforms.
+    ///
+    /// Each manifest is serialized to JSON and referenced by the SHA-256
+    /// digest and size of that serialization, so the manifests must be pushed
+    /// in exactly this serialization.
+    pub fn from_image_manifests(
+        entries: Vec<(ManifestV2_2, ManifestPlatformV2_2)>,
+    ) -> Result<Self, ManifestBuildError> {
+        let manifests = entries
+            .into_iter()
+            .map(|(manifest, platform)| {
+                let (size, digest) = manifest_size_and_digest(&manifest)?;
+
+                Ok(ImageIndexEntry {
+                    media_type: manifest.media_type,
+                    size,
+                    digest,
+                    platform: Some(platform),
+                    urls: None,
+                    annotations: None,
+                })
+            })
+            .collect::<Result<Vec<_>, ManifestBuildError>>()?;
+
+        Ok(ImageIndex {
+            manifests,
+            ..Default::default()
+        })
+    }
+
+    /// Get a platform manifest for the current platform from an image index.
+    ///
+    /// Entries are selected the same way as from a manifest list. Entries
+    /// without a platform cannot be selected.
+    pub fn get_current_platform_manifest<T>(
+        &self,
+        image: &Image,
+    ) -> Result<ManifestV2_2, RegistryError>
+    where
+        T: ImageSelector,
+    {
+        self.to_manifest_list()
+            .get_current_platform_manifest::<T>(image)
+    }
+
+    /// Return the platform specific entries of the index as a manifest list.
+    fn to_manifest_list(&self) -> ManifestListV2_2 {
+        ManifestListV2_2::new(
+            self.manifests
+                .iter()
+                .filter_map(|entry| {
+                    entry
+                        .platform
+                        .as_ref()
+                        .map(|platform| ManifestListEntryV2_2 {
+                            media_type: entry.media_type.clone(),
+                            size: entry.size,
+                            digest: entry.digest.clone(),
+                            platform: platform.clone(),
+                        })
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Return the size and SHA-256 digest of the JSON serialization of a manifest.
+fn manifest_size_and_digest(
+    manifest: &ManifestV2_2,
+) -> Result<(usize, Digest), ManifestBuildError> {
+    let data = serde_json::to_vec(manifest).map_err(ManifestBuildError::JsonError)?;
+
+    Ok((data.len(), Digest::compute(DigestAlgorithm::Sha256, &data)))
+}
+
+impl Default for ImageIndex {
+    fn default() -> Self {
+        ImageIndex {
+            schema: 2,
+            media_type: Some("application/vnd.oci.image.index.v1+json".into()),
+            manifests: Vec::new(),
+            annotations: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -681,93 +2513,1198 @@ mod tests {
     #[test]
     fn test_manifest_v1() {
         let test_data = include_str!("test/manifest-v2-1.test.json");
-
+
+        let manifest: ManifestV2_1 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert_eq!(manifest.schema, 1);
+        assert_eq!(manifest.name, "hello-world");
+        assert_eq!(manifest.tag, "latest");
+        assert_eq!(manifest.architecture, "amd64");
+        assert_eq!(manifest.layers.len(), 4);
+    }
+
+    #[test]
+    fn test_manifest_v1_to_json() {
+        let test_data = include_str!("test/manifest-v2-1.test.json");
+
+        let manifest: ManifestV2_1 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        let json = manifest.to_json().expect("Could not serialize manifest");
+
+        let expected: serde_json::Value = serde_json::from_str(test_data).unwrap();
+        let actual: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual, expected);
+
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+        let json = manifest.to_json().expect("Could not serialize manifest");
+        let actual: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_manifest_v1_to_v2_2() {
+        let test_data = include_str!("test/manifest-v2-1.test.json");
+
+        let manifest: ManifestV2_1 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        let converted = manifest.to_v2_2();
+
+        assert_eq!(converted.schema, 2);
+        assert_eq!(
+            converted.media_type,
+            "application/vnd.docker.distribution.manifest.v2+json"
+        );
+        assert!(converted.config.digest().is_zero());
+        assert_eq!(converted.config.size(), 0);
+
+        assert_eq!(converted.layers.len(), manifest.layers.len());
+        for (converted, original) in converted.layers.iter().zip(manifest.layers.iter().rev()) {
+            assert_eq!(&converted.digest, &original.inner);
+            assert_eq!(converted.media_type, LayerMediaType::TarGz);
+        }
+        assert_eq!(
+            converted.layers[1].digest.to_string(),
+            "sha256:cc8567d70002e957612902a8e985ea129d831ebe04057d88fb644857caa45d11"
+        );
+    }
+
+    #[test]
+    fn test_manifest_v2() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert_eq!(manifest.schema, 2);
+        assert_eq!(
+            manifest.media_type,
+            "application/vnd.docker.distribution.manifest.v2+json"
+        );
+
+        assert_eq!(
+            manifest.config.media_type,
+            "application/vnd.docker.container.image.v1+json"
+        );
+        assert_eq!(manifest.config.size, 7023);
+        assert_eq!(manifest.config.digest.algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(
+            manifest.config.digest.hex,
+            "b5b2b2c507a0944348e0303114d8d93aaaa081732b86451d9bce1f432a537bc7"
+        );
+
+        assert_eq!(manifest.layers.len(), 3);
+
+        assert_eq!(
+            manifest.layers[0],
+            LayerV2_2 {
+                media_type: LayerMediaType::TarGz,
+                size: 32654,
+                digest: "sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f"
+                    .parse()
+                    .expect("Could not parse reference digest"),
+                urls: None,
+                annotations: None,
+            }
+        );
+
+        assert_eq!(
+            manifest.layers[1],
+            LayerV2_2 {
+                media_type: LayerMediaType::TarGz,
+                size: 16724,
+                digest: "sha256:3c3a4604a545cdc127456d94e421cd355bca5b528f4a9c1905b15da2eb4a4c6b"
+                    .parse()
+                    .expect("Could not parse reference digest"),
+                urls: None,
+                annotations: None,
+            }
+        );
+
+        assert_eq!(
+            manifest.layers[2],
+            LayerV2_2 {
+                media_type: LayerMediaType::TarGz,
+                size: 73109,
+                digest: "sha256:ec4b8955958665577945c89419d1af06b5f7636b4ac3da7f12184802ad867736"
+                    .parse()
+                    .expect("Could not parse reference digest"),
+                urls: None,
+                annotations: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_v2_for_json_blob() {
+        let data = include_bytes!("test/config-v1.test.json");
+        let expected_digest = Digest::compute(DigestAlgorithm::Sha256, data);
+
+        let config = ConfigV2_2::for_json_blob(data);
+        assert_eq!(
+            config.media_type,
+            "application/vnd.oci.image.config.v1+json"
+        );
+        assert_eq!(config.size, data.len());
+        assert_eq!(config.digest, expected_digest);
+
+        let config = ConfigV2_2::for_docker_json_blob(data);
+        assert_eq!(
+            config.media_type,
+            "application/vnd.docker.container.image.v1+json"
+        );
+        assert_eq!(config.size, data.len());
+        assert_eq!(config.digest, expected_digest);
+
+        let config = ConfigV2_2::for_json_blob(b"{}");
+        assert_eq!(config.size, 2);
+        assert_eq!(
+            config.digest.to_string(),
+            "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+
+    #[test]
+    fn test_layer_v2_for_blob() {
+        let layer = LayerV2_2::for_blob(LayerMediaType::Tar, b"hello world")
+            .expect("Could not create layer descriptor");
+
+        assert_eq!(
+            layer,
+            LayerV2_2 {
+                media_type: LayerMediaType::Tar,
+                size: 11,
+                digest: "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+                    .parse()
+                    .expect("Could not parse reference digest"),
+                urls: None,
+                annotations: None,
+            }
+        );
+
+        match LayerV2_2::for_blob(LayerMediaType::TarGz, b"") {
+            Err(ManifestBuildError::EmptyLayer) => {}
+            other => panic!("unexpected result for empty blob: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_manifest_v2_from_config_and_layers() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let reference: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        let manifest = ManifestV2_2::from_config_and_layers(
+            reference.config.clone(),
+            reference.layers.clone(),
+        );
+        assert_eq!(manifest.schema, 2);
+        assert_eq!(
+            manifest.media_type,
+            "application/vnd.oci.image.manifest.v1+json"
+        );
+
+        let json = serde_json::to_string(&manifest).expect("Could not serialize manifest");
+        let parsed: ManifestV2_2 =
+            serde_json::from_str(&json).expect("Could not deserialize manifest");
+        assert_eq!(parsed.schema, 2);
+        assert_eq!(parsed.media_type, manifest.media_type);
+        assert_eq!(parsed.config, reference.config);
+        assert_eq!(parsed.layers, reference.layers);
+        assert!(parsed.annotations.is_none());
+
+        let manifest = ManifestV2_2::for_docker(reference.config.clone(), reference.layers.clone());
+        assert_eq!(manifest.schema, 2);
+        assert_eq!(
+            manifest.media_type,
+            "application/vnd.docker.distribution.manifest.v2+json"
+        );
+
+        let json = serde_json::to_string(&manifest).expect("Could not serialize manifest");
+        let parsed: ManifestV2 = json.parse().expect("Could not parse manifest");
+        assert_eq!(ManifestV2Schema::from(parsed), ManifestV2Schema::Schema2);
+    }
+
+    #[test]
+    fn test_manifest_v2_with_schema_version() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        let manifest = manifest
+            .with_schema_version(2)
+            .expect("Could not set schema version 2");
+        assert_eq!(manifest.schema, 2);
+
+        for version in &[0, 1, 3] {
+            let manifest: ManifestV2_2 =
+                serde_json::from_str(test_data).expect("Could not deserialize manifest");
+            match manifest.with_schema_version(*version) {
+                Err(ManifestBuildError::UnsupportedSchemaVersion(v)) => assert_eq!(v, *version),
+                other => panic!("unexpected result for version {}: {:?}", version, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_manifest_v2_applicable_registry_layers() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        assert_eq!(manifest.applicable_registry_layers().count(), 3);
+        assert!(manifest.all_layers_fetchable_from_registry());
+
+        let mut layers = manifest.layers.clone();
+        layers[1].media_type = LayerMediaType::NondistributableTarGz;
+        layers[2].media_type = LayerMediaType::NondistributableTar;
+        layers[2].urls = Some(vec!["https://example.com/layer.tar".into()]);
+
+        let mixed = ManifestV2_2::for_docker(manifest.config.clone(), layers);
+        let applicable: Vec<&LayerV2_2> = mixed.applicable_registry_layers().collect();
+        assert_eq!(applicable, vec![&mixed.layers[0], &mixed.layers[2]]);
+        assert!(!mixed.all_layers_fetchable_from_registry());
+    }
+
+    #[test]
+    fn test_manifest_v2_match_diff_ids() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        let diff_ids: Vec<Digest> = vec![
+            "sha256:5f70bf18a086007016e948b04aed3b82103a36bea41755b6cddfaf10ace3c6ef",
+            "sha256:cc8567d70002e957612902a8e985ea129d831ebe04057d88fb644857caa45d11",
+            "sha256:6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b",
+        ]
+        .into_iter()
+        .map(|d| d.parse().expect("Could not parse reference digest"))
+        .collect();
+
+        let pairs = manifest
+            .match_diff_ids(&diff_ids)
+            .expect("Could not match DiffIDs");
+        assert_eq!(pairs.len(), 3);
+        for (i, (layer, diff_id)) in pairs.into_iter().enumerate() {
+            assert_eq!(layer, &manifest.layers[i]);
+            assert_eq!(diff_id, &diff_ids[i]);
+        }
+
+        match manifest.match_diff_ids(&diff_ids[..2]) {
+            Err(DiffIdError::LayerCountMismatch { layers, diff_ids }) => {
+                assert_eq!(layers, 3);
+                assert_eq!(diff_ids, 2);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_manifest_v2_sizes() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+
+        let layers: Vec<_> = manifest
+            .layers()
+            .expect("Could not get layers")
+            .map(|l| l.size())
+            .collect();
+        assert_eq!(layers, vec![Some(32654), Some(16724), Some(73109)]);
+
+        let manifest = ManifestV2_2::try_from(manifest).expect("Manifest is not schema 2");
+        assert_eq!(manifest.config.size(), 7023);
+        assert_eq!(manifest.layers[0].size(), 32654);
+
+        let test_data = include_str!("test/manifest-v2-1.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+        assert!(manifest
+            .layers()
+            .expect("Could not get layers")
+            .all(|l| l.size().is_none()));
+    }
+
+    #[test]
+    fn test_manifest_error_display() {
+        let error = "not a digest"
+            .parse::<Digest>()
+            .expect_err("Invalid digest was parsed");
+        match error {
+            ManifestError::DigestParseFailed(..) => {}
+            ref other => panic!("Unexpected error: {:?}", other),
+        }
+        let message = error.to_string();
+        assert!(message.starts_with("Parsing digest failed: 'not a digest'"));
+        assert!(!message.contains("Error {"));
+        assert!(std::error::Error::source(&error).is_some());
+
+        let error = "{"
+            .parse::<ManifestV2>()
+            .expect_err("Invalid JSON was parsed");
+        match error {
+            ManifestError::JsonError(_) => {}
+            ref other => panic!("Unexpected error: {:?}", other),
+        }
+        assert!(!error.to_string().contains("Error("));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn test_errors_are_std_errors() {
+        fn parse(s: &str) -> Result<Digest, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(s.parse::<Digest>()?)
+        }
+
+        let error = parse("not a digest").expect_err("Invalid digest was parsed");
+        assert!(error.to_string().starts_with("Parsing digest failed"));
+
+        let error: Box<dyn std::error::Error> = Box::new(RegistryError::ManifestError(
+            ManifestError::InvalidSchemaVersion(3),
+        ));
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_manifest_list_filters() {
+        use crate::image::spec::{GoArch, GoOs};
+
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        assert_eq!(list.filter_by_os("linux").len(), 2);
+        assert!(list.filter_by_os("windows").is_empty());
+        assert!(list.filter_by_os("not-an-os").is_empty());
+
+        let amd64 = list.filter_by_arch("x86_64");
+        assert_eq!(amd64.len(), 1);
+        assert_eq!(amd64[0].platform.architecture, GoArch::AMD64);
+        assert!(list.filter_by_arch("arm64").is_empty());
+
+        let ppc64le = list.filter_by_platform("ppc64le", "linux");
+        assert_eq!(ppc64le.len(), 1);
+        assert_eq!(ppc64le[0].digest, list.manifests[0].digest);
+        assert!(list.filter_by_platform("ppc64le", "windows").is_empty());
+
+        let architectures: HashSet<_> = vec![GoArch::PPC64le, GoArch::AMD64].into_iter().collect();
+        assert_eq!(list.architectures(), architectures);
+        let operating_systems: HashSet<_> = vec![GoOs::Linux].into_iter().collect();
+        assert_eq!(list.operating_systems(), operating_systems);
+    }
+
+    #[test]
+    fn test_current_platform_selector() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        match CurrentPlatformSelector::select_manifest(&list) {
+            Some(entry) => {
+                assert!(entry.platform.current_platform_matches());
+                assert_eq!(
+                    list.get_current_platform_manifest_digest::<CurrentPlatformSelector>(),
+                    Some(&entry.digest)
+                );
+            }
+            None => assert!(list
+                .manifests
+                .iter()
+                .all(|m| !m.platform.current_platform_matches())),
+        }
+    }
+
+    #[test]
+    fn test_probe_oci_image_manifest() {
+        let manifest = ManifestV2_2::from_config_and_layers(
+            ConfigV2_2::for_json_blob(b"{}"),
+            vec![LayerV2_2::for_blob(LayerMediaType::TarGz, b"hello").unwrap()],
+        );
+        let json = serde_json::to_string(&manifest).unwrap();
+
+        assert_eq!(
+            probe_manifest_v2_schema(&json).expect("Could not probe manifest"),
+            ManifestV2Schema::Schema2
+        );
+        match json.parse::<ManifestV2>() {
+            Ok(ManifestV2::Schema2(parsed)) => assert_eq!(parsed.layers, manifest.layers),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let index = include_str!("test/image-index-v1.test.json");
+        assert_eq!(
+            probe_manifest_v2_schema(index).expect("Could not probe index"),
+            ManifestV2Schema::Schema2OciIndex
+        );
+    }
+
+    #[test]
+    fn test_manifest_v2_serialize() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+
+        let inner: ManifestV2_2 = serde_json::from_str(test_data).unwrap();
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap(),
+            serde_json::to_value(&inner).unwrap()
+        );
+        assert_eq!(
+            manifest.to_json().unwrap(),
+            serde_json::to_string(&inner).unwrap()
+        );
+
+        let pretty = manifest
+            .to_json_pretty()
+            .expect("Could not serialize manifest");
+        assert!(pretty.contains('\n'));
+        let reparsed: ManifestV2 = pretty.parse().expect("Could not reparse manifest");
+        assert_eq!(
+            serde_json::to_value(&reparsed).unwrap(),
+            serde_json::to_value(&manifest).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_manifest_v2_try_from() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+
+        let manifest = ManifestV2::try_from(test_data).expect("Could not convert &str");
+        assert_eq!(ManifestV2Schema::from(&manifest), ManifestV2Schema::Schema2);
+
+        let manifest =
+            ManifestV2::try_from(test_data.to_string()).expect("Could not convert String");
+        assert_eq!(ManifestV2Schema::from(&manifest), ManifestV2Schema::Schema2);
+
+        let document = serde_json::json!({
+            "name": "test",
+            "manifest": serde_json::from_str::<serde_json::Value>(test_data).unwrap(),
+        });
+        let manifest =
+            ManifestV2::try_from(document["manifest"].clone()).expect("Could not convert Value");
+        assert_eq!(ManifestV2Schema::from(&manifest), ManifestV2Schema::Schema2);
+
+        match ManifestV2::try_from(serde_json::json!({"schemaVersion": 3})) {
+            Err(ManifestError::InvalidSchemaVersion(3)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_manifest_list_has_no_layers() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest list");
+
+        match manifest.layers() {
+            Err(RegistryError::ManifestError(ManifestError::NotAnImageManifest(
+                ManifestV2Schema::Schema2List,
+            ))) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => panic!("Manifest list has layers"),
+        }
+    }
+
+    #[test]
+    fn test_image_diff() {
+        let layer = |data: &[u8]| LayerV2_2::for_blob(LayerMediaType::TarGz, data).unwrap();
+        let config = ConfigV2_2::for_json_blob(b"{}");
+
+        let old = ManifestV2_2::from_config_and_layers(
+            config.clone(),
+            vec![layer(b"base"), layer(b"app-1.24")],
+        );
+        let new = ManifestV2_2::from_config_and_layers(
+            config,
+            vec![layer(b"base"), layer(b"app-1.25"), layer(b"extra")],
+        );
+
+        let diff = image_diff(&old, &new);
+        assert_eq!(diff.unchanged, vec![layer(b"base")]);
+        assert_eq!(diff.added, vec![layer(b"app-1.25"), layer(b"extra")]);
+        assert_eq!(diff.removed, vec![layer(b"app-1.24")]);
+
+        let diff = image_diff(&old, &old);
+        assert_eq!(diff.unchanged, old.layers);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_v2_total_size() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        let layer_sizes: Vec<usize> = manifest.layers.iter().map(LayerV2_2::size).collect();
+        assert_eq!(layer_sizes, vec![32654, 16724, 73109]);
+
+        assert_eq!(manifest.compressed_layer_size(), 32654 + 16724 + 73109);
+        assert_eq!(manifest.total_size(), 7023 + 32654 + 16724 + 73109);
+    }
+
+    #[test]
+    fn test_manifest_v2_deduped_layers() {
+        let layer = |data: &[u8]| LayerV2_2::for_blob(LayerMediaType::Tar, data).unwrap();
+
+        let manifest = ManifestV2_2::from_config_and_layers(
+            ConfigV2_2::for_json_blob(b"{}"),
+            vec![
+                layer(b"base"),
+                layer(b"app"),
+                layer(b"base"),
+                layer(b"data"),
+            ],
+        );
+        assert!(manifest.has_duplicate_layers());
+        assert_eq!(
+            manifest.deduped_layers(),
+            vec![&layer(b"base"), &layer(b"app"), &layer(b"data")]
+        );
+
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        assert!(!manifest.has_duplicate_layers());
+        assert_eq!(manifest.deduped_layers().len(), 3);
+    }
+
+    #[test]
+    fn test_manifest_v2_verify_digests() {
+        let config = b"{}";
+        let layer = b"hello world";
+
+        let manifest = ManifestV2_2::from_config_and_layers(
+            ConfigV2_2::for_json_blob(config),
+            vec![LayerV2_2::for_blob(LayerMediaType::Tar, layer)
+                .expect("Could not create layer descriptor")],
+        );
+
+        manifest
+            .verify_config_digest(config)
+            .expect("Could not verify config digest");
+        manifest
+            .verify_layer_digest(0, layer)
+            .expect("Could not verify layer digest");
+
+        let error = manifest
+            .verify_config_digest(b"{ }")
+            .expect_err("tampered config was verified");
+        assert_eq!(error.expected, manifest.config.digest);
+        assert_eq!(
+            error.actual,
+            Digest::compute(DigestAlgorithm::Sha256, b"{ }")
+        );
+        assert_eq!(error.context, "config");
+
+        let error = manifest
+            .verify_layer_digest(0, b"hello tampered world")
+            .expect_err("tampered layer was verified");
+        assert_eq!(error.expected, manifest.layers[0].digest);
+        assert_eq!(error.context, "layer 0");
+    }
+
+    #[test]
+    fn test_manifest_v2_is_scratch() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        assert!(!manifest.is_scratch());
+
+        let empty_layer = LayerV2_2 {
+            media_type: LayerMediaType::TarGz,
+            size: 32,
+            digest: EMPTY_LAYER_DIGEST
+                .parse()
+                .expect("Could not parse empty layer digest"),
+            urls: None,
+            annotations: None,
+        };
+
+        let scratch = ManifestV2_2::for_docker(manifest.config.clone(), vec![empty_layer.clone()]);
+        assert!(scratch.is_scratch());
+
+        let two_layers = ManifestV2_2::for_docker(
+            manifest.config.clone(),
+            vec![empty_layer.clone(), empty_layer],
+        );
+        assert!(!two_layers.is_scratch());
+
+        let no_layers = ManifestV2_2::for_docker(manifest.config.clone(), vec![]);
+        assert!(!no_layers.is_scratch());
+    }
+
+    #[test]
+    fn test_manifest_v2_subject() {
+        let test_data = include_str!("test/manifest-v2-2-subject.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        let subject = manifest.subject().expect("manifest has no subject");
+        assert_eq!(
+            subject.media_type,
+            "application/vnd.oci.image.manifest.v1+json"
+        );
+        assert_eq!(subject.size, 7682);
+
+        let subject_digest: Digest =
+            "sha256:5b0bcabd1ed22e9fb1310cf6c2dec7cdef19f0ad69efa1f392e94a4333501270"
+                .parse()
+                .expect("Could not parse reference digest");
+        assert!(manifest.is_attestation_for(&subject_digest));
+        assert!(!manifest.is_attestation_for(&manifest.config.digest));
+
+        let json = serde_json::to_string(&manifest).expect("Could not serialize manifest");
+        let parsed: ManifestV2_2 =
+            serde_json::from_str(&json).expect("Could not deserialize manifest");
+        assert_eq!(parsed.subject(), manifest.subject());
+    }
+
+    #[test]
+    fn test_manifest_v2_without_subject() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert!(manifest.subject().is_none());
+        assert!(!manifest.is_attestation_for(&manifest.config.digest));
+
+        let json = serde_json::to_string(&manifest).expect("Could not serialize manifest");
+        assert!(!json.contains("subject"));
+    }
+
+    #[test]
+    fn test_manifest_v2_oci_annotations() {
+        let test_data = include_str!("test/manifest-v2-2-annotations.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        let annotations = manifest.oci_annotations();
+        assert_eq!(
+            annotations.title.as_ref().map(String::as_str),
+            Some("hello-world")
+        );
+        assert_eq!(
+            annotations.created.as_ref().map(String::as_str),
+            Some("2019-05-01T12:34:56Z")
+        );
+        assert_eq!(
+            annotations.authors.as_ref().map(String::as_str),
+            Some("Alyssa P. Hacker <alyspdev@example.com>")
+        );
+        assert_eq!(
+            annotations.licenses.as_ref().map(String::as_str),
+            Some("MIT")
+        );
+        assert_eq!(
+            annotations.ref_name.as_ref().map(String::as_str),
+            Some("latest")
+        );
+        assert_eq!(annotations.description, None);
+        assert_eq!(annotations.vendor, None);
+    }
+
+    #[test]
+    fn test_manifest_v2_layer_annotations() {
+        let test_data = include_str!("test/manifest-v2-2-annotations.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        let annotations = manifest.annotations().expect("Manifest has no annotations");
+        assert_eq!(
+            annotations.get("com.example.key").map(String::as_str),
+            Some("value")
+        );
+
+        let annotations = manifest.layers[0]
+            .annotations()
+            .expect("Layer has no annotations");
+        assert_eq!(
+            annotations
+                .get("org.opencontainers.image.source")
+                .map(String::as_str),
+            Some("https://github.com/docker-library/hello-world")
+        );
+        assert_eq!(manifest.layers[1].annotations(), None);
+
+        let serialized = serde_json::to_value(&manifest).unwrap();
+        assert!(serialized["layers"][0].get("annotations").is_some());
+        assert!(serialized["layers"][1].get("annotations").is_none());
+    }
+
+    #[test]
+    fn test_manifest_v2_created_at() {
+        let test_data = include_str!("test/manifest-v2-2-annotations.test.json");
+        let mut manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert_eq!(manifest.created_at_string(), Some("2019-05-01T12:34:56Z"));
+        assert_eq!(
+            manifest.created_at(),
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_556_714_096))
+        );
+
+        manifest.annotations.as_mut().unwrap().insert(
+            "org.opencontainers.image.created".into(),
+            "yesterday".into(),
+        );
+        assert_eq!(manifest.created_at_string(), Some("yesterday"));
+        assert_eq!(manifest.created_at(), None);
+
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        assert_eq!(manifest.created_at_string(), None);
+        assert_eq!(manifest.created_at(), None);
+    }
+
+    #[test]
+    fn test_manifest_v2_oci_annotations_missing() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert!(manifest.annotations.is_none());
+        assert_eq!(manifest.oci_annotations(), OciAnnotations::default());
+    }
+
+    #[test]
+    fn test_manifest_v2_schema_string() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert_eq!(
+            manifest.to_schema_string(),
+            "Schema2[config=sha256:b5b2b2c507a0..., layers=3, size=122487]"
+        );
+        assert_eq!(manifest.to_string(), manifest.to_schema_string());
+    }
+
+    #[test]
+    fn test_manifest_list_v2_schema_string() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        assert_eq!(
+            manifest_list.to_schema_string(),
+            "Schema2List[platforms=2: linux/ppc64le, linux/amd64]"
+        );
+        assert_eq!(manifest_list.to_string(), manifest_list.to_schema_string());
+    }
+
+    #[test]
+    fn test_descriptor_display() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+
+        assert_eq!(
+            manifest.config.to_string(),
+            "config(digest=sha256:b5b2b2c507a0944348e0303114d8d93aaaa081732b86451d9bce1f432a537bc7, size=7023B)"
+        );
+        assert_eq!(
+            manifest.layers[0].to_string(),
+            "layer(digest=sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f, size=32654B, type=tar+gzip)"
+        );
+
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+        assert_eq!(
+            manifest_list.manifests[0].to_string(),
+            "manifest(digest=sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f, size=7143B, platform=linux/ppc64le)"
+        );
+
+        let test_data = include_str!("test/manifest-v2-1.test.json");
+        let manifest: ManifestV2_1 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        assert_eq!(manifest.to_string(), "manifest(schema=1, layers=4)");
+        assert_eq!(
+            manifest.layers[0].to_string(),
+            "layer(digest=sha256:5f70bf18a086007016e948b04aed3b82103a36bea41755b6cddfaf10ace3c6ef)"
+        );
+    }
+
+    #[test]
+    fn test_platform_spec_string() {
+        for spec in &[
+            "linux/amd64",
+            "linux/arm/v7",
+            "linux/arm64/v8",
+            "windows/amd64",
+            "freebsd/386",
+            "linux/ppc64le",
+        ] {
+            let platform = ManifestPlatformV2_2::from_platform_spec_string(spec)
+                .expect("Could not parse platform");
+            assert_eq!(&platform.to_platform_spec_string(), spec);
+        }
+
+        let platform = ManifestPlatformV2_2::from_platform_spec_string("linux/arm/v7")
+            .expect("Could not parse platform");
+        assert_eq!(platform.os, go::GoOs::Linux);
+        assert_eq!(platform.architecture, go::GoArch::ARM);
+        assert_eq!(platform.variant, Some("v7".into()));
+
+        let mut platform = ManifestPlatformV2_2::from_platform_spec_string("linux/amd64")
+            .expect("Could not parse platform");
+        assert_eq!(platform.variant, None);
+        platform.variant = Some("".into());
+        assert_eq!(platform.to_platform_spec_string(), "linux/amd64");
+    }
+
+    #[test]
+    fn test_manifest_list_entry_accessors() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        let entry = &manifest_list.manifests[1];
+        assert_eq!(
+            entry.media_type(),
+            "application/vnd.docker.image.manifest.v2+json"
+        );
+        assert_eq!(entry.size(), 7682);
+        assert_eq!(
+            entry.digest().to_string(),
+            "sha256:5b0bcabd1ed22e9fb1310cf6c2dec7cdef19f0ad69efa1f392e94a4333501270"
+        );
+
+        let platform = entry.platform();
+        assert_eq!(platform.architecture(), go::GoArch::AMD64);
+        assert_eq!(platform.os(), go::GoOs::Linux);
+        assert_eq!(platform.features(), Some(&["sse4".to_string()][..]));
+        assert_eq!(platform.variant(), None);
+        assert_eq!(platform.os_version(), None);
+        assert_eq!(platform.os_features(), None);
+    }
+
+    #[test]
+    fn test_platform_string() {
+        let platform = ManifestPlatformV2_2::from_platform_spec_string("linux/arm/v7")
+            .expect("Could not parse platform");
+        assert_eq!(platform.to_string(), "linux/arm/v7");
+
+        let parsed: ManifestPlatformV2_2 = "linux/amd64".parse().expect("Could not parse platform");
+        assert_eq!(parsed.to_platform_spec_string(), "linux/amd64");
+
+        match "linux".parse::<ManifestPlatformV2_2>() {
+            Err(PlatformParseError::InvalidFormat(s)) => assert_eq!(s, "linux"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_platform_spec_string_invalid() {
+        for spec in &["", "linux", "linux/arm/", "linux/arm/v7/x"] {
+            match ManifestPlatformV2_2::from_platform_spec_string(spec) {
+                Err(PlatformParseError::InvalidFormat(s)) => assert_eq!(&s, spec),
+                other => panic!("unexpected result for '{}': {:?}", spec, other),
+            }
+        }
+
+        match ManifestPlatformV2_2::from_platform_spec_string("plan10/amd64") {
+            Err(PlatformParseError::InvalidOs(os)) => assert_eq!(os, "plan10"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        match ManifestPlatformV2_2::from_platform_spec_string("linux/z80") {
+            Err(PlatformParseError::InvalidArch(arch)) => assert_eq!(arch, "z80"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_manifest_list_v2() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        assert_eq!(manifest_list.schema, 2);
+        assert_eq!(
+            manifest_list.media_type,
+            "application/vnd.docker.distribution.manifest.list.v2+json"
+        );
+        assert_eq!(manifest_list.manifests.len(), 2);
+    }
+
+    #[test]
+    fn test_manifest_v2_new() {
+        let config = ConfigV2_2::for_docker_json_blob(b"{}");
+        let layer = LayerV2_2::for_blob(LayerMediaType::TarGz, b"layer").unwrap();
+
+        let manifest = ManifestV2_2::new(config.clone(), vec![layer.clone()]);
+        assert_eq!(manifest.schema, 2);
+        assert_eq!(
+            manifest.media_type,
+            "application/vnd.docker.distribution.manifest.v2+json"
+        );
+        assert_eq!(manifest.config, config);
+        assert_eq!(manifest.layers, vec![layer]);
+        assert!(manifest.annotations.is_none());
+    }
+
+    #[test]
+    fn test_manifest_v2_identity() {
+        let test_data = include_str!("test/manifest-v2-2-annotations.test.json");
+        let manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        let mut annotated: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        annotated.layers[0]
+            .annotations
+            .as_mut()
+            .expect("Layer has no annotations")
+            .insert("org.opencontainers.image.source".into(), "elsewhere".into());
+
+        assert_eq!(manifest, annotated);
+
+        let mut manifests = HashSet::new();
+        manifests.insert(manifest);
+        assert!(!manifests.insert(annotated));
+
+        let other = ManifestV2_2::new(ConfigV2_2::for_docker_json_blob(b"{}"), vec![]);
+        assert!(manifests.insert(other));
+        assert_eq!(manifests.len(), 2);
+    }
+
+    #[test]
+    fn test_manifest_v2_identity_converted() {
+        let test_data = include_str!("test/manifest-v2-1.test.json");
         let manifest: ManifestV2_1 =
             serde_json::from_str(test_data).expect("Could not deserialize manifest");
 
-        assert_eq!(manifest.schema, 1);
-        assert_eq!(manifest.name, "hello-world");
-        assert_eq!(manifest.tag, "latest");
-        assert_eq!(manifest.architecture, "amd64");
-        assert_eq!(manifest.layers.len(), 4);
+        let converted = manifest.to_v2_2();
+        let mut truncated = manifest.to_v2_2();
+        truncated.layers.pop();
+
+        assert_eq!(converted, manifest.to_v2_2());
+        assert_ne!(converted, truncated);
+
+        let mut manifests = HashSet::new();
+        assert!(manifests.insert(converted));
+        assert!(manifests.insert(truncated));
+        assert!(!manifests.insert(manifest.to_v2_2()));
     }
 
     #[test]
-    fn test_manifest_v2() {
-        let test_data = include_str!("test/manifest-v2-2.test.json");
+    fn test_manifest_list_v2_new() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        let rebuilt = ManifestListV2_2::new(manifest_list.manifests);
+        assert_eq!(rebuilt.schema, 2);
+        assert_eq!(
+            rebuilt.media_type,
+            "application/vnd.docker.distribution.manifest.list.v2+json"
+        );
+        assert_eq!(rebuilt.manifests.len(), 2);
+    }
 
+    #[test]
+    fn test_manifest_list_from_image_manifests() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
         let manifest: ManifestV2_2 =
             serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        let manifest_json = serde_json::to_vec(&manifest).expect("Could not serialize manifest");
 
-        assert_eq!(manifest.schema, 2);
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let reference: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+        let platform = reference.manifests[1].platform.clone();
+
+        let manifest_list = ManifestListV2_2::from_image_manifests(vec![(manifest, platform)])
+            .expect("Could not build manifest list");
+        assert_eq!(manifest_list.schema, 2);
         assert_eq!(
-            manifest.media_type,
-            "application/vnd.docker.distribution.manifest.v2+json"
+            manifest_list.media_type,
+            "application/vnd.docker.distribution.manifest.list.v2+json"
         );
 
+        let json = serde_json::to_string(&manifest_list).expect("Could not serialize list");
+        let parsed: ManifestListV2_2 =
+            serde_json::from_str(&json).expect("Could not deserialize manifest list");
+
+        assert_eq!(parsed.manifests.len(), 1);
+        let entry = &parsed.manifests[0];
         assert_eq!(
-            manifest.config.media_type,
-            "application/vnd.docker.container.image.v1+json"
+            entry.media_type,
+            "application/vnd.docker.distribution.manifest.v2+json"
         );
-        assert_eq!(manifest.config.size, 7023);
-        assert_eq!(manifest.config.digest.algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(entry.size, manifest_json.len());
         assert_eq!(
-            manifest.config.digest.hex,
-            "b5b2b2c507a0944348e0303114d8d93aaaa081732b86451d9bce1f432a537bc7"
+            entry.digest,
+            Digest::compute(DigestAlgorithm::Sha256, &manifest_json)
         );
+        assert_eq!(entry.platform, reference.manifests[1].platform);
+        parsed.validate().expect("built manifest list is invalid");
+    }
 
-        assert_eq!(manifest.layers.len(), 3);
+    #[test]
+    fn test_image_index_from_image_manifests() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let mut manifest: ManifestV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest");
+        manifest.media_type = "application/vnd.oci.image.manifest.v1+json".into();
+        let manifest_json = serde_json::to_vec(&manifest).expect("Could not serialize manifest");
+        let platform: ManifestPlatformV2_2 = "linux/arm64/v8".parse().unwrap();
+
+        let index = ImageIndex::from_image_manifests(vec![(manifest, platform.clone())])
+            .expect("Could not build image index");
+
+        let json = ManifestV2::Schema2OciIndex(index)
+            .to_json()
+            .expect("Could not serialize image index");
+        let parsed = ImageIndex::try_from(
+            json.parse::<ManifestV2>()
+                .expect("Could not parse serialized image index"),
+        )
+        .expect("Manifest is not an image index");
 
+        assert_eq!(parsed.manifests.len(), 1);
+        let entry = &parsed.manifests[0];
         assert_eq!(
-            manifest.layers[0],
-            LayerV2_2 {
-                media_type: LayerMediaType::TarGz,
-                size: 32654,
-                digest: "sha256:e692418e4cbaf90ca69d05a66403747baa33ee08806650b51fab815ad7fc331f"
-                    .parse()
-                    .expect("Could not parse reference digest"),
-                urls: None,
-            }
+            entry.media_type,
+            "application/vnd.oci.image.manifest.v1+json"
         );
-
+        assert_eq!(entry.size, manifest_json.len());
         assert_eq!(
-            manifest.layers[1],
-            LayerV2_2 {
-                media_type: LayerMediaType::TarGz,
-                size: 16724,
-                digest: "sha256:3c3a4604a545cdc127456d94e421cd355bca5b528f4a9c1905b15da2eb4a4c6b"
-                    .parse()
-                    .expect("Could not parse reference digest"),
-                urls: None,
-            }
+            entry.digest,
+            Digest::compute(DigestAlgorithm::Sha256, &manifest_json)
         );
+        assert_eq!(entry.platform, Some(platform));
+    }
 
-        assert_eq!(
-            manifest.layers[2],
-            LayerV2_2 {
-                media_type: LayerMediaType::TarGz,
-                size: 73109,
-                digest: "sha256:ec4b8955958665577945c89419d1af06b5f7636b4ac3da7f12184802ad867736"
-                    .parse()
-                    .expect("Could not parse reference digest"),
-                urls: None,
-            }
-        );
+    #[test]
+    fn test_manifest_platform_matches_platform() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        let amd64 = &manifest_list.manifests[1].platform;
+        assert!(amd64.matches_platform("amd64", "linux"));
+        assert!(amd64.matches_platform("x86_64", "linux"));
+        assert!(!amd64.matches_platform("arm64", "linux"));
+        assert!(!amd64.matches_platform("amd64", "windows"));
+        assert!(!amd64.matches_platform("z80", "linux"));
+
+        let ppc64le = &manifest_list.manifests[0].platform;
+        assert!(ppc64le.matches_platform("ppc64le", "linux"));
+        assert!(!ppc64le.matches_platform("amd64", "linux"));
     }
 
     #[test]
-    fn test_manifest_list_v2() {
+    fn test_manifest_list_find_manifest_by_digest() {
         let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        let digest: Digest =
+            "sha256:5b0bcabd1ed22e9fb1310cf6c2dec7cdef19f0ad69efa1f392e94a4333501270"
+                .parse()
+                .expect("Could not parse reference digest");
+        let entry = manifest_list
+            .find_manifest_by_digest(&digest)
+            .expect("Could not find manifest by digest");
+        assert_eq!(entry.platform.architecture, go::GoArch::AMD64);
+        assert!(manifest_list.contains_digest(&digest));
+
+        let missing: Digest =
+            "sha256:b5b2b2c507a0944348e0303114d8d93aaaa081732b86451d9bce1f432a537bc7"
+                .parse()
+                .expect("Could not parse reference digest");
+        assert!(manifest_list.find_manifest_by_digest(&missing).is_none());
+        assert!(!manifest_list.contains_digest(&missing));
+    }
 
+    #[test]
+    fn test_manifest_list_find_manifests_for_platform() {
+        let test_data = include_str!("test/manifest-list-v2-2-duplicate.test.json");
         let manifest_list: ManifestListV2_2 =
             serde_json::from_str(test_data).expect("Could not deserialize manifest list");
 
-        assert_eq!(manifest_list.schema, 2);
         assert_eq!(
-            manifest_list.media_type,
-            "application/vnd.docker.distribution.manifest.list.v2+json"
+            manifest_list
+                .find_manifests_for_platform("amd64", "linux")
+                .len(),
+            2
         );
-        assert_eq!(manifest_list.manifests.len(), 2);
+        assert_eq!(
+            manifest_list
+                .find_manifests_for_platform("ppc64le", "linux")
+                .len(),
+            2
+        );
+        assert!(manifest_list
+            .find_manifests_for_platform("amd64", "windows")
+            .is_empty());
+        assert!(manifest_list
+            .find_manifests_for_platform("not-an-arch", "linux")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_manifest_list_validate() {
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        assert!(!manifest_list.has_duplicate_platforms());
+        manifest_list
+            .validate()
+            .expect("valid manifest list failed validation");
+    }
+
+    #[test]
+    fn test_manifest_list_duplicate_platforms() {
+        let test_data = include_str!("test/manifest-list-v2-2-duplicate.test.json");
+        let manifest_list: ManifestListV2_2 =
+            serde_json::from_str(test_data).expect("Could not deserialize manifest list");
+
+        assert!(manifest_list.has_duplicate_platforms());
+
+        match manifest_list.validate_no_duplicate_platforms() {
+            Err(ManifestValidationError::DuplicatePlatforms(conflicts)) => {
+                assert_eq!(conflicts.len(), 2);
+
+                let (ref platform, ref indices) = conflicts[0];
+                assert_eq!(platform.architecture, go::GoArch::AMD64);
+                assert_eq!(platform.os, go::GoOs::Linux);
+                assert_eq!(indices, &vec![0, 2]);
+
+                let (ref platform, ref indices) = conflicts[1];
+                assert_eq!(platform.architecture, go::GoArch::PPC64le);
+                assert_eq!(platform.os, go::GoOs::Linux);
+                assert_eq!(indices, &vec![1, 3]);
+            }
+            other => panic!("unexpected validation result: {:?}", other),
+        }
+
+        manifest_list
+            .validate()
+            .expect_err("manifest list with duplicate platforms passed validation");
+    }
+
+    #[test]
+    fn test_manifest_list_validate_empty() {
+        let manifest_list: ManifestListV2_2 = serde_json::from_str(
+            r#"{
+                "schemaVersion": 2,
+                "mediaType": "application/vnd.docker.distribution.manifest.list.v2+json",
+                "manifests": []
+            }"#,
+        )
+        .expect("Could not deserialize manifest list");
+
+        match manifest_list.validate() {
+            Err(ManifestValidationError::EmptyManifestList) => {}
+            other => panic!("unexpected validation result: {:?}", other),
+        }
     }
 
     #[test]
@@ -842,6 +3779,169 @@ mod tests {
         assert_eq!(schema, ManifestV2Schema::Schema2);
     }
 
+    #[test]
+    fn test_manifest_v2_to_json_roundtrip() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+        let json = manifest.to_json().expect("Could not serialize manifest");
+        let reparsed: ManifestV2 = json.parse().expect("Could not parse serialized manifest");
+
+        let manifest = ManifestV2_2::try_from(manifest).expect("Manifest is not schema 2");
+        let reparsed = ManifestV2_2::try_from(reparsed).expect("Manifest is not schema 2");
+        assert_eq!(manifest.media_type, reparsed.media_type);
+        assert_eq!(manifest.config.digest, reparsed.config.digest);
+        assert_eq!(manifest.layers, reparsed.layers);
+
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let list: ManifestV2 = test_data.parse().expect("Could not parse manifest list");
+        let json = list.to_json().expect("Could not serialize manifest list");
+        let reparsed: ManifestV2 = json.parse().expect("Could not parse serialized list");
+
+        let list = ManifestListV2_2::try_from(list).expect("Manifest is not a list");
+        let reparsed = ManifestListV2_2::try_from(reparsed).expect("Manifest is not a list");
+        assert_eq!(list.media_type, reparsed.media_type);
+        assert_eq!(list.manifests.len(), reparsed.manifests.len());
+        for (a, b) in list.manifests.iter().zip(reparsed.manifests.iter()) {
+            assert_eq!(a.digest, b.digest);
+        }
+
+        let test_data = include_str!("test/manifest-v2-1.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+        let json = manifest.to_json().expect("Could not serialize manifest");
+        let reparsed: ManifestV2 = json.parse().expect("Could not parse serialized manifest");
+
+        let manifest = ManifestV2_1::try_from(manifest).expect("Manifest is not schema 1");
+        let reparsed = ManifestV2_1::try_from(reparsed).expect("Manifest is not schema 1");
+        assert_eq!(manifest.layers.len(), reparsed.layers.len());
+    }
+
+    #[test]
+    fn test_manifest_v2_try_from_wrong_schema() {
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse manifest");
+
+        match ManifestListV2_2::try_from(manifest) {
+            Err(ManifestError::UnexpectedSchema(ManifestV2Schema::Schema2)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_image_index() {
+        let test_data = include_str!("test/image-index-v1.test.json");
+
+        let schema = probe_manifest_v2_schema(test_data).expect("could not probe manifest schema");
+        assert_eq!(schema, ManifestV2Schema::Schema2OciIndex);
+
+        let manifest: ManifestV2 = test_data.parse().expect("Could not parse image index");
+        let index = ImageIndex::try_from(manifest).expect("Manifest is not an image index");
+
+        assert_eq!(index.schema, 2);
+        assert_eq!(
+            index.media_type.as_ref().map(String::as_str),
+            Some("application/vnd.oci.image.index.v1+json")
+        );
+        assert_eq!(index.manifests.len(), 2);
+        assert_eq!(
+            index.manifests[1]
+                .platform
+                .as_ref()
+                .map(|p| p.to_platform_spec_string()),
+            Some("linux/amd64".to_owned())
+        );
+        assert_eq!(
+            index.manifests[1]
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get("org.opencontainers.image.ref.name"))
+                .map(String::as_str),
+            Some("latest")
+        );
+        assert_eq!(
+            index
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get("com.example.key1"))
+                .map(String::as_str),
+            Some("value1")
+        );
+
+        let json = ManifestV2::Schema2OciIndex(index.clone())
+            .to_json()
+            .expect("Could not serialize image index");
+        let reparsed = ImageIndex::try_from(
+            json.parse::<ManifestV2>()
+                .expect("Could not parse serialized image index"),
+        )
+        .expect("Manifest is not an image index");
+        assert_eq!(reparsed, index);
+    }
+
+    #[test]
+    fn test_manifest_v2_from_reader() {
+        let test_data = include_str!("test/manifest-v2-1.test.json");
+        let manifest = ManifestV2::from_reader(test_data.as_bytes())
+            .expect("Could not read schema 1 manifest");
+        assert_eq!(ManifestV2Schema::from(&manifest), ManifestV2Schema::Schema1);
+
+        let test_data = include_str!("test/manifest-v2-2.test.json");
+        let manifest = ManifestV2::from_reader(test_data.as_bytes())
+            .expect("Could not read schema 2 manifest");
+        assert_eq!(ManifestV2Schema::from(&manifest), ManifestV2Schema::Schema2);
+
+        let test_data = include_str!("test/manifest-list-v2-2.test.json");
+        let manifest =
+            ManifestV2::from_reader(test_data.as_bytes()).expect("Could not read manifest list");
+        assert_eq!(
+            ManifestV2Schema::from(&manifest),
+            ManifestV2Schema::Schema2List
+        );
+    }
+
+    #[test]
+    fn test_manifest_v2_from_reader_invalid() {
+        match ManifestV2::from_reader(&br#"{"schemaVersion": 3}"#[..]) {
+            Err(ManifestError::InvalidSchemaVersion(3)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        match ManifestV2::from_reader(&b"{"[..]) {
+            Err(ManifestError::JsonError(_)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_manifest_error_from_io_error() {
+        fn read() -> Result<(), ManifestError> {
+            std::fs::read("/nonexistent/manifest.json")?;
+            Ok(())
+        }
+
+        match read() {
+            Err(ManifestError::IoError(error)) => {
+                assert_eq!(error.kind(), std::io::ErrorKind::NotFound)
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_io_error_from_manifest_error() {
+        fn parse(data: &str) -> std::io::Result<ManifestV2> {
+            Ok(data.parse::<ManifestV2>()?)
+        }
+
+        let error = parse(r#"{"schemaVersion": 3}"#).expect_err("invalid manifest was parsed");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "Invalid Schema Version: 3");
+
+        let error: std::io::Error =
+            ManifestError::IoError(std::io::Error::new(std::io::ErrorKind::NotFound, "missing"))
+                .into();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
     #[test]
     fn test_probe_manifest_schema2_list() {
         let test_data = include_str!("test/manifest-list-v2-2.test.json");
@@ -887,6 +3987,240 @@ mod tests {
         assert_eq!(&digest.to_string(), test_data)
     }
 
+    #[test]
+    fn test_digest_hasher() {
+        use std::io::Write;
+
+        let data = b"hello world";
+
+        let mut hasher = DigestAlgorithm::Sha256.new_hasher();
+        assert_eq!(hasher.algorithm(), DigestAlgorithm::Sha256);
+        hasher
+            .write_all(&data[..5])
+            .expect("Could not write to hasher");
+        hasher
+            .write_all(&data[5..])
+            .expect("Could not write to hasher");
+        let digest = hasher.finalize();
+
+        assert_eq!(digest, Digest::compute(DigestAlgorithm::Sha256, data));
+        assert_eq!(
+            &digest.to_string(),
+            "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn test_digest_from_reader() {
+        // Larger than the buffer used by `io::copy`, to hash multiple reads.
+        let data = vec![0x42; 64 * 1024 + 1];
+
+        for algorithm in &[
+            DigestAlgorithm::Sha256,
+            DigestAlgorithm::Sha384,
+            DigestAlgorithm::Sha512,
+        ] {
+            let digest =
+                Digest::from_reader(*algorithm, &data[..]).expect("Could not read content");
+            assert_eq!(digest, Digest::from_bytes(*algorithm, &data));
+        }
+
+        let error = Digest::from_reader(DigestAlgorithm::Sha256, FailingReader)
+            .expect_err("Read error was not reported");
+        assert_eq!(error.kind(), std::io::ErrorKind::Other);
+    }
+
+    struct FailingReader;
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "read failed",
+            ))
+        }
+    }
+
+    #[test]
+    fn test_digest_sha384_sha512() {
+        let sha384 = "sha384:fdbd8e75a67f29f701a4e040385e2e23986303ea10239211af907fcbb83578b3e417cb71ce646efd0819dd8c088de1bd";
+        let sha512 = "sha512:309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f";
+
+        let digest: Digest = sha384.parse().expect("Could not parse sha384 digest");
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha384);
+        assert_eq!(digest.hex.len(), DigestAlgorithm::Sha384.hash_length());
+        assert_eq!(&digest.to_string(), sha384);
+        assert!(digest.verify_bytes(b"hello world"));
+
+        let digest: Digest = sha512.parse().expect("Could not parse sha512 digest");
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha512);
+        assert_eq!(digest.hex.len(), DigestAlgorithm::Sha512.hash_length());
+        assert_eq!(&digest.to_string(), sha512);
+        assert!(digest.verify_bytes(b"hello world"));
+        assert!(!digest.verify_bytes(b"hello tampered world"));
+
+        assert_eq!(
+            Digest::compute(DigestAlgorithm::Sha512, b"hello world"),
+            digest
+        );
+
+        match "sha1024:deadbeef".parse::<Digest>() {
+            Err(ManifestError::InvalidDigestAlgorithm(algorithm)) => {
+                assert_eq!(algorithm, "sha1024")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_digest_verify_reader() {
+        use std::io::Read;
+
+        let data = b"hello world";
+        let digest = Digest::compute(DigestAlgorithm::Sha256, data);
+
+        let mut reader = digest
+            .verify_reader(&data[..])
+            .expect("Could not create verified reader");
+        let mut content = Vec::new();
+        reader
+            .read_to_end(&mut content)
+            .expect("Could not read from verified reader");
+        assert_eq!(&content[..], &data[..]);
+        reader.finish().expect("Could not verify content");
+
+        let mut reader = digest
+            .verify_reader(&b"hello tampered world"[..])
+            .expect("Could not create verified reader");
+        std::io::copy(&mut reader, &mut std::io::sink())
+            .expect("Could not read from verified reader");
+        match reader.finish() {
+            Err(DigestMismatchError {
+                expected, actual, ..
+            }) => {
+                assert_eq!(expected, digest);
+                assert_eq!(
+                    actual,
+                    Digest::compute(DigestAlgorithm::Sha256, b"hello tampered world")
+                );
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_layer_media_type_zstd() {
+        for (media_type, expected, distributable) in &[
+            (
+                "application/vnd.oci.image.layer.v1.tar+zstd",
+                LayerMediaType::TarZstd,
+                true,
+            ),
+            (
+                "application/vnd.oci.image.layer.nondistributable.v1.tar+zstd",
+                LayerMediaType::NondistributableTarZstd,
+                false,
+            ),
+        ] {
+            let parsed: LayerMediaType = media_type.parse().unwrap();
+            assert_eq!(&parsed, expected);
+            assert_eq!(&parsed.to_string(), media_type);
+            assert!(parsed.is_zstd());
+            assert!(parsed.is_compressed());
+            assert_eq!(parsed.is_distributable(), *distributable);
+        }
+
+        assert!(!LayerMediaType::TarGz.is_zstd());
+        assert!(!LayerMediaType::Tar.is_zstd());
+    }
+
+    #[test]
+    fn test_layer_media_type_is_compressed() {
+        assert!(LayerMediaType::TarGz.is_compressed());
+        assert!(LayerMediaType::NondistributableTarGz.is_compressed());
+        assert!(LayerMediaType::TarZstd.is_compressed());
+        assert!(LayerMediaType::NondistributableTarZstd.is_compressed());
+
+        assert!(!LayerMediaType::Tar.is_compressed());
+        assert!(!LayerMediaType::NondistributableTar.is_compressed());
+        assert!(!LayerMediaType::Other("application/octet-stream".into()).is_compressed());
+    }
+
+    #[test]
+    #[cfg(feature = "blake3")]
+    fn test_digest_blake3() {
+        let digest = Digest::from_bytes_blake3(b"hello world");
+        assert_eq!(digest.algorithm, DigestAlgorithm::Blake3);
+        assert_eq!(digest.hex.len(), DigestAlgorithm::Blake3.hash_length());
+        assert!(digest.verify_bytes(b"hello world"));
+        assert!(!digest.verify_bytes(b"hello tampered world"));
+
+        let parsed: Digest = digest
+            .to_string()
+            .parse()
+            .expect("Could not parse blake3 digest");
+        assert_eq!(parsed, digest);
+        assert!(digest.to_string().starts_with("blake3:"));
+
+        assert!(Digest::zero(DigestAlgorithm::Blake3).is_zero());
+    }
+
+    #[test]
+    fn test_digest_zero() {
+        let zero = Digest::zero(DigestAlgorithm::Sha256);
+
+        assert_eq!(zero.algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(zero.hex.len(), 64);
+        assert!(zero.is_zero());
+        assert_eq!(
+            zero.to_string()
+                .parse::<Digest>()
+                .expect("Could not parse zero digest"),
+            zero
+        );
+
+        assert!(!Digest::compute(DigestAlgorithm::Sha256, b"").is_zero());
+    }
+
+    #[test]
+    fn test_digest_truncated_hex() {
+        let hex = "6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b";
+        let digest: Digest = format!("sha256:{}", hex)
+            .parse()
+            .expect("Could not parse digest");
+
+        assert_eq!(digest.truncated_hex(0), "");
+        assert_eq!(digest.truncated_hex(12), "6c3c624b58db");
+        assert_eq!(digest.truncated_hex(64), hex);
+        assert_eq!(digest.truncated_hex(100), hex);
+        assert_eq!(digest.short_form(), "6c3c624b58db");
+        assert_eq!(digest.short_display().to_string(), "sha256:6c3c624b58db");
+    }
+
+    #[test]
+    fn test_digest_from_hex() {
+        let hex = "6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b";
+        let digest =
+            Digest::from_hex(DigestAlgorithm::Sha256, hex).expect("Could not create digest");
+        assert_eq!(digest, format!("sha256:{}", hex).parse().unwrap());
+
+        for invalid in &[
+            "",
+            &hex[1..],
+            hex.to_uppercase().as_str(),
+            "6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3g",
+        ] {
+            match Digest::from_hex(DigestAlgorithm::Sha256, invalid) {
+                Err(ManifestError::InvalidDigestHex(DigestAlgorithm::Sha256, h)) => {
+                    assert_eq!(&h, invalid)
+                }
+                other => panic!("Unexpected result for {:?}: {:?}", invalid, other),
+            }
+        }
+
+        assert!(Digest::from_hex(DigestAlgorithm::Sha384, hex).is_err());
+    }
+
     #[test]
     fn test_parse_digest_fail() {
         "foobar"
@@ -898,5 +4232,53 @@ mod tests {
         "sha256:xxxyyyzzz"
             .parse::<Digest>()
             .expect_err("parsing digest with non-hex string succeeded");
+        "sha256:6C3C624B58DBBCD3C0DD82B4C53F04194D1247C6EEBDAAB7C610CF7D66709B3B"
+            .parse::<Digest>()
+            .expect_err("parsing digest with uppercase hex succeeded");
+
+        match "sha256:deadbeef".parse::<Digest>() {
+            Err(ManifestError::InvalidDigestHex(DigestAlgorithm::Sha256, hex)) => {
+                assert_eq!(hex, "deadbeef")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_digest_lengths() {
+        assert_eq!(DigestAlgorithm::Sha256.byte_length(), 32);
+        assert_eq!(DigestAlgorithm::Sha384.byte_length(), 48);
+        assert_eq!(DigestAlgorithm::Sha512.byte_length(), 64);
+
+        for &algorithm in &[
+            DigestAlgorithm::Sha256,
+            DigestAlgorithm::Sha384,
+            DigestAlgorithm::Sha512,
+        ] {
+            let digest = Digest::compute(algorithm, b"hello world");
+            assert_eq!(digest.expected_hex_length(), algorithm.byte_length() * 2);
+            assert_eq!(digest.hex.len(), digest.expected_hex_length());
+        }
+    }
+
+    #[test]
+    fn test_parse_digest_normalized() {
+        let expected = "sha256:6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b";
+
+        let digest = Digest::parse_normalized(
+            "sha256:6C3C624B58DBBCD3C0DD82B4C53F04194D1247C6EEBDAAB7C610CF7D66709B3B",
+        )
+        .expect("Could not parse uppercase digest");
+        assert_eq!(&digest.to_string(), expected);
+
+        let digest = Digest::parse_normalized(expected).expect("Could not parse lowercase digest");
+        assert_eq!(&digest.to_string(), expected);
+
+        Digest::parse_normalized(
+            "SHA256:6c3c624b58dbbcd3c0dd82b4c53f04194d1247c6eebdaab7c610cf7d66709b3b",
+        )
+        .expect_err("algorithm should not be normalized");
+        Digest::parse_normalized("sha256:XYZ")
+            .expect_err("parsing normalized digest with non-hex string succeeded");
     }
 }
@@ -1,15 +1,52 @@
-use crate::distribution::{Registry, RegistryError};
+use crate::distribution::{Registry, RegistryAuth, RegistryError};
+use crate::store::ContentStore;
+use std::io::Read;
+use thiserror::Error;
 mod go;
 
 pub mod manifest;
 pub mod spec;
 use manifest::Digest;
+use manifest::Layer as _;
 pub use manifest::ManifestV2;
 
+/// Size of the chunks blobs are uploaded in by [Image::copy_to].
+const COPY_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum ImageError {
+    #[error("Missing required field: {0}")]
+    MissingField(&'static str),
+
+    #[error("Registry Error: {0}")]
+    RegistryError(#[source] RegistryError),
+}
+
+/// The registry an image was fetched from, either borrowed from the caller or
+/// created by an [ImageBuilder].
+#[derive(Debug)]
+enum RegistryRef<'a> {
+    Borrowed(&'a Registry),
+    Owned(Box<Registry>),
+}
+
+impl std::ops::Deref for RegistryRef<'_> {
+    type Target = Registry;
+
+    fn deref(&self) -> &Registry {
+        match self {
+            RegistryRef::Borrowed(registry) => *registry,
+            RegistryRef::Owned(registry) => &**registry,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Image<'a> {
-    registry: &'a Registry,
+    registry: RegistryRef<'a>,
     name: String,
+    reference: String,
     manifest: ManifestV2,
 }
 
@@ -35,6 +72,11 @@ impl ImageSelector for ImagePlatformSelector {
     }
 }
 
+/// The default [ImageSelector], selecting the image for the current platform.
+///
+/// This is the same selector as [ImagePlatformSelector].
+pub type CurrentPlatformSelector = ImagePlatformSelector;
+
 /// Utility image selector for tests, always takes the first available image manifest.
 pub struct TestImageSelector {}
 
@@ -73,43 +115,60 @@ impl<'a> Image<'a> {
     where
         IS: ImageSelector,
     {
-        let name = name.to_owned();
-
-        let url = format!("{}/v2/{}/manifests/{}", registry.url, name, reference);
+        Self::fetch::<IS>(RegistryRef::Borrowed(registry), name, reference)
+    }
 
-        // Make sure we only accept schema 2, if we don't set this, we will get
-        // schema1 by default.
-        // For now, do not support Manifest Lists.
-        let accept_types = vec![
-            "application/vnd.oci.distribution.manifest.list.v2+json",
-            "application/vnd.oci.distribution.manifest.v2+json",
-            "application/vnd.docker.distribution.manifest.list.v2+json",
-            "application/vnd.docker.distribution.manifest.v2+json",
-        ];
+    /// Create a builder for an image that owns its registry
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    /// use opencontainers::Image;
+    ///
+    /// let image = Image::builder()
+    ///     .registry("https://registry-1.docker.io")
+    ///     .name("library/hello-world")
+    ///     .tag("latest")
+    ///     .build()
+    ///     .expect("Could not get image");
+    /// ```
+    pub fn builder() -> ImageBuilder {
+        ImageBuilder::default()
+    }
 
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::ACCEPT,
-            accept_types.join(",").parse().unwrap(),
-        );
+    fn fetch<IS>(
+        registry: RegistryRef<'a>,
+        name: &str,
+        reference: &str,
+    ) -> Result<Self, RegistryError>
+    where
+        IS: ImageSelector,
+    {
+        let name = name.to_owned();
 
         let manifest = registry
-            .get(&url, Some(&headers))?
-            .text()
-            .map_err(RegistryError::ReqwestError)?
-            .parse()
-            .map_err(RegistryError::ManifestError)?;
+            .get_manifest_with_content_type(&name, reference)?
+            .manifest;
 
         let mut image = Self {
             registry,
             name,
+            reference: reference.to_owned(),
             manifest,
         };
 
-        if let ManifestV2::Schema2List(ref l) = image.manifest {
-            image.manifest = ManifestV2::Schema2(l.get_current_platform_manifest::<IS>(&image)?);
+        let platform_manifest = match image.manifest {
+            ManifestV2::Schema2List(ref l) => Some(l.get_current_platform_manifest::<IS>(&image)?),
+            ManifestV2::Schema2OciIndex(ref i) => {
+                Some(i.get_current_platform_manifest::<IS>(&image)?)
+            }
+            _ => None,
         };
 
+        if let Some(manifest) = platform_manifest {
+            image.manifest = ManifestV2::Schema2(manifest);
+        }
+
         Ok(image)
     }
 
@@ -129,14 +188,136 @@ impl<'a> Image<'a> {
         &self.manifest
     }
 
+    /// Return the name of the image in its repository
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# use opencontainers::image::TestImageSelector as ImagePlatformSelector;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let image = registry.image::<ImagePlatformSelector>("library/hello-world", "latest")
+    ///     .expect("Could not get image");
+    /// assert_eq!(image.name(), "library/hello-world");
+    /// ```
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the URL of the registry the image was fetched from
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# use opencontainers::image::TestImageSelector as ImagePlatformSelector;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let image = registry.image::<ImagePlatformSelector>("library/hello-world", "latest")
+    ///     .expect("Could not get image");
+    /// assert_eq!(image.registry_url(), "https://registry-1.docker.io");
+    /// ```
+    pub fn registry_url(&self) -> &str {
+        &self.registry.url
+    }
+
+    /// Return the tag the image was fetched by
+    ///
+    /// Returns `None` if the image was fetched by digest.
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# use opencontainers::image::TestImageSelector as ImagePlatformSelector;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let image = registry.image::<ImagePlatformSelector>("library/hello-world", "latest")
+    ///     .expect("Could not get image");
+    /// assert_eq!(image.tag(), Some("latest"));
+    /// ```
+    pub fn tag(&self) -> Option<&str> {
+        match self.reference.parse::<Digest>() {
+            Ok(_) => None,
+            Err(_) => Some(&self.reference),
+        }
+    }
+
+    /// List all tags of the image's repository
+    ///
+    /// # Example
+    /// ```
+    ///# extern crate opencontainers;
+    ///# use opencontainers::Registry;
+    ///# use opencontainers::image::TestImageSelector as ImagePlatformSelector;
+    ///# let registry = Registry::new("https://registry-1.docker.io");
+    /// let image = registry.image::<ImagePlatformSelector>("library/hello-world", "latest")
+    ///     .expect("Could not get image");
+    /// let tags = image.list_tags().expect("Could not list tags");
+    /// assert!(tags.contains(&"latest".to_string()));
+    /// ```
+    pub fn list_tags(&self) -> Result<Vec<String>, RegistryError> {
+        self.registry.list_tags(&self.name)
+    }
+
+    /// Upload a layer to the image's repository in a single chunk
+    ///
+    /// Returns the SHA-256 digest of the layer, for use in a manifest.
+    pub fn push_layer(&self, data: &[u8]) -> Result<Digest, RegistryError> {
+        let digest = Digest::compute(manifest::DigestAlgorithm::Sha256, data);
+
+        let mut upload = self.registry.start_upload(&self.name)?;
+        upload.upload_chunk(data)?;
+        upload.finish(&digest)?;
+
+        Ok(digest)
+    }
+
+    /// Upload a manifest to the image's repository, tagging it with the
+    /// reference the image was fetched by
+    ///
+    /// All blobs referenced by the manifest must have been uploaded before.
+    pub fn push_manifest(&self, manifest: &manifest::ManifestV2_2) -> Result<(), RegistryError> {
+        self.registry
+            .push_manifest(&self.name, &self.reference, manifest)
+    }
+
+    /// Copy the image to the repository of another image
+    ///
+    /// The config and all layers are uploaded to the destination repository,
+    /// skipping blobs that already exist there, and the manifest is pushed
+    /// with the reference of `dest`. Only schema 2 manifests can be copied.
+    pub fn copy_to(&self, dest: &Image) -> Result<(), RegistryError> {
+        let manifest = match self.manifest() {
+            ManifestV2::Schema2(m) => m,
+            other => return Err(RegistryError::UnsupportedManifestSchema(other.into())),
+        };
+
+        let digests = std::iter::once(manifest.config.digest())
+            .chain(manifest.layers.iter().map(|l| l.digest()));
+        let mut chunk = Vec::new();
+
+        for digest in digests {
+            if dest.registry.blob_exists(&dest.name, digest)? {
+                info!("Blob {} exists in {}, skipping", digest, dest.name);
+                continue;
+            }
+
+            let blob = self.registry.fetch_blob(&self.name, digest)?;
+            let mut upload = dest.registry.start_upload(&dest.name)?;
+            upload.upload_reader(blob, &mut chunk, COPY_CHUNK_SIZE)?;
+            upload.finish(digest)?;
+        }
+
+        dest.push_manifest(manifest)
+    }
+
     pub fn get_blob(&self, digest: &Digest) -> Result<reqwest::Response, RegistryError> {
         let url = format!("{}/v2/{}/blobs/{}", self.registry.url, self.name, digest);
 
         self.registry.get(&url, None)
     }
 
-    /// Return the image runtime configuration
-    pub fn config(&self) -> Result<spec::ImageV1, RegistryError> {
+    /// Fetch the raw image configuration blob
+    fn config_blob(&self) -> Result<String, RegistryError> {
         match manifest::ManifestV2Schema::from(self.manifest()) {
             manifest::ManifestV2Schema::Schema2 => {}
             other => return Err(RegistryError::UnsupportedManifestSchema(other)),
@@ -147,14 +328,66 @@ impl<'a> Image<'a> {
             _ => unreachable!(),
         };
 
-        self.get_blob(config_digest)?
-            .text()
-            .map_err(RegistryError::ReqwestError)?
+        let mut config = String::new();
+        self.registry
+            .fetch_blob(&self.name, config_digest)?
+            .read_to_string(&mut config)
+            .map_err(RegistryError::IoError)?;
+
+        Ok(config)
+    }
+
+    /// Return the image runtime configuration
+    pub fn config(&self) -> Result<spec::ImageV1, RegistryError> {
+        self.config_blob()?
             .parse()
             .map_err(RegistryError::ImageSpecError)
     }
 
+    /// Return the image configuration
+    ///
+    /// This is the same as [Image::config].
+    pub fn get_config(&self) -> Result<spec::ImageV1, RegistryError> {
+        self.config()
+    }
+
+    /// Fetch the manifest of the image from the registry without blocking
+    ///
+    /// See [Registry::get_manifest_async].
+    #[cfg(feature = "async")]
+    pub async fn get_manifest_async(&self) -> Result<ManifestV2, RegistryError> {
+        self.registry
+            .get_manifest_async(&self.name, &self.reference)
+            .await
+            .map(|response| response.manifest)
+    }
+
+    /// Get a layer without blocking, decompressing if necessary
+    ///
+    /// The layer blob is fetched into memory and verified against its digest
+    /// before it is decompressed. Unlike [Image::get_layer], the URLs of the
+    /// layer are not tried.
+    #[cfg(feature = "async")]
+    pub async fn get_layer_async<L>(
+        &self,
+        layer: &L,
+    ) -> Result<tar::Archive<Box<dyn std::io::Read>>, RegistryError>
+    where
+        L: crate::image::manifest::Layer + ?Sized,
+    {
+        let blob = self
+            .registry
+            .fetch_blob_async(&self.name, layer.digest())
+            .await?;
+        decompress_layer(layer.media_type(), Box::new(std::io::Cursor::new(blob)))
+    }
+
     /// Get a layer, decompressing if necessary
+    ///
+    /// If the registry does not have the layer blob, it is fetched from the
+    /// URLs of the layer in order, as is common for foreign layers. Blobs
+    /// fetched from these URLs are verified against the layer digest when
+    /// they are read to the end.
     pub fn get_layer<L>(
         &self,
         layer: &L,
@@ -162,17 +395,235 @@ impl<'a> Image<'a> {
     where
         L: crate::image::manifest::Layer + ?Sized,
     {
-        let response = self.get_blob(layer.digest())?;
+        let response = self.fetch_layer_blob(layer)?;
+        decompress_layer(layer.media_type(), response)
+    }
+
+    /// Fetch a layer blob from the registry, falling back to the URLs of the
+    /// layer if the registry responds with 404 Not Found.
+    fn fetch_layer_blob<L>(&self, layer: &L) -> Result<Box<dyn std::io::Read>, RegistryError>
+    where
+        L: crate::image::manifest::Layer + ?Sized,
+    {
+        let not_found = match self.registry.fetch_blob(&self.name, layer.digest()) {
+            Err(e @ RegistryError::HttpStatus { status: 404, .. }) => e,
+            other => return other,
+        };
 
-        if let Some(media_type) = layer.media_type() {
-            if !media_type.is_gzipped() {
-                // No need to wrap reader
-                return Ok(tar::Archive::new(Box::new(response)));
+        let mut last_error = not_found;
+        for url in layer.urls().unwrap_or_default() {
+            match self.registry.fetch_url(url, layer.digest()) {
+                Ok(response) => {
+                    info!("Fetched layer {} from {}", layer.digest(), url);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    warn!(
+                        "Could not fetch layer {} from {}: {}",
+                        layer.digest(),
+                        url,
+                        e
+                    );
+                    last_error = e;
+                }
             }
         }
 
-        // Otherwise, wrap in a flate2::read::GzDecoder
-        let decoder = flate2::read::GzDecoder::new(response);
-        Ok(tar::Archive::new(Box::new(decoder)))
+        Err(last_error)
+    }
+
+    /// Get the blob of a layer as it is stored in the registry, without
+    /// decompressing it
+    ///
+    /// The blob is streamed from the response, so it is never held in memory
+    /// as a whole. If the registry has a disk cache, the blob is streamed from
+    /// the cache instead.
+    pub fn get_layer_stream<L>(&self, layer: &L) -> Result<impl std::io::Read, RegistryError>
+    where
+        L: crate::image::manifest::Layer + ?Sized,
+    {
+        self.registry.fetch_blob(&self.name, layer.digest())
+    }
+
+    /// Pull the image manifest and all layer blobs
+    ///
+    /// Manifest lists and image indexes have already been resolved to a
+    /// platform manifest when the image was fetched, and schema 1 manifests
+    /// are converted to schema 2 (see [manifest::ManifestV2_1::to_v2_2]). Layer blobs are kept as stored in
+    /// the registry, i.e. compressed, and are verified against their digest.
+    pub fn pull(&self) -> Result<PulledImage, RegistryError> {
+        let manifest = match self.manifest() {
+            ManifestV2::Schema1(m) => m.to_v2_2(),
+            ManifestV2::Schema2(m) => m.clone(),
+            other => return Err(RegistryError::UnsupportedManifestSchema(other.into())),
+        };
+
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|layer| -> Result<_, RegistryError> {
+                let mut blob = vec![];
+                self.registry
+                    .fetch_blob(&self.name, layer.digest())?
+                    .read_to_end(&mut blob)
+                    .map_err(RegistryError::IoError)?;
+
+                manifest::DigestMismatchError::check(layer.digest(), &blob, "layer")?;
+
+                Ok((layer.clone(), blob))
+            })
+            .collect::<Result<Vec<_>, RegistryError>>()?;
+
+        Ok(PulledImage { manifest, layers })
+    }
+}
+
+/// An image pulled by [Image::pull], with all layer blobs held in memory.
+#[derive(Debug)]
+pub struct PulledImage {
+    /// The single-platform manifest of the image.
+    pub manifest: manifest::ManifestV2_2,
+
+    /// The layers of the manifest along with their compressed blobs, in the
+    /// order of the manifest.
+    pub layers: Vec<(manifest::LayerV2_2, Vec<u8>)>,
+}
+
+/// Wrap a layer blob in a tar archive, decompressing if necessary.
+fn decompress_layer(
+    media_type: Option<&manifest::LayerMediaType>,
+    response: Box<dyn std::io::Read>,
+) -> Result<tar::Archive<Box<dyn std::io::Read>>, RegistryError> {
+    if let Some(media_type) = media_type {
+        if media_type.is_zstd() {
+            let decoder =
+                zstd::stream::read::Decoder::new(response).map_err(RegistryError::IoError)?;
+            return Ok(tar::Archive::new(Box::new(decoder)));
+        }
+
+        // Assume unknown media types are gzipped.
+        let compressed = match media_type {
+            manifest::LayerMediaType::Other(_) => true,
+            other => other.is_compressed(),
+        };
+
+        if !compressed {
+            // No need to wrap reader
+            return Ok(tar::Archive::new(response));
+        }
+    }
+
+    // Otherwise, wrap in a flate2::read::GzDecoder
+    let decoder = flate2::read::GzDecoder::new(response);
+    Ok(tar::Archive::new(Box::new(decoder)))
+}
+
+/// Builder for an [Image] together with the [Registry] it is fetched from.
+///
+/// The registry URL and image name are required. The tag defaults to
+/// `latest`.
+#[derive(Debug, Default)]
+pub struct ImageBuilder {
+    registry: Option<String>,
+    name: Option<String>,
+    tag: Option<String>,
+    auth: RegistryAuth,
+}
+
+impl ImageBuilder {
+    /// Set the URL of the registry, without a trailing slash.
+    pub fn registry(mut self, url: &str) -> Self {
+        self.registry = Some(url.into());
+        self
+    }
+
+    /// Set the name of the image, e.g. `library/hello-world`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the tag of the image.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Set the authentication used for the registry.
+    pub fn auth(mut self, auth: RegistryAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Fetch the image, selecting the image for the current platform from
+    /// manifest lists.
+    pub fn build(self) -> Result<Image<'static>, ImageError> {
+        self.build_with_selector::<CurrentPlatformSelector>()
+    }
+
+    /// Fetch the image, selecting from manifest lists with the given
+    /// [ImageSelector].
+    pub fn build_with_selector<IS>(self) -> Result<Image<'static>, ImageError>
+    where
+        IS: ImageSelector,
+    {
+        let url = self.registry.ok_or(ImageError::MissingField("registry"))?;
+        let name = self.name.ok_or(ImageError::MissingField("name"))?;
+        let tag = self.tag.unwrap_or_else(|| "latest".into());
+
+        let registry = Registry::new(&url).with_auth(self.auth);
+
+        Image::fetch::<IS>(RegistryRef::Owned(Box::new(registry)), &name, &tag)
+            .map_err(ImageError::RegistryError)
+    }
+}
+
+/// An image whose layers are cached in a [ContentStore].
+///
+/// Layers are only fetched from the registry if they are not in the store
+/// yet, and are verified against their digest before they are added to it.
+pub struct CachedImage<'a> {
+    image: Image<'a>,
+    store: Box<dyn ContentStore>,
+}
+
+impl<'a> std::fmt::Debug for CachedImage<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "CachedImage {{ image: {:?} }}", self.image)
+    }
+}
+
+impl<'a> CachedImage<'a> {
+    pub fn new(image: Image<'a>, store: Box<dyn ContentStore>) -> Self {
+        CachedImage { image, store }
+    }
+
+    /// Return the cached image
+    pub fn image(&self) -> &Image<'a> {
+        &self.image
+    }
+
+    /// Get a layer, decompressing if necessary
+    ///
+    /// The layer is read from the content store, and fetched from the
+    /// registry into the store first if it is missing.
+    pub fn get_layer<L>(
+        &self,
+        layer: &L,
+    ) -> Result<tar::Archive<Box<dyn std::io::Read>>, RegistryError>
+    where
+        L: crate::image::manifest::Layer + ?Sized,
+    {
+        let digest = layer.digest();
+
+        if !self.store.exists(digest) {
+            let mut response = self.image.registry.fetch_blob(&self.image.name, digest)?;
+            self.store
+                .put(digest, &mut response)
+                .map_err(RegistryError::StoreError)?;
+        }
+
+        let blob = self.store.get(digest).map_err(RegistryError::StoreError)?;
+        decompress_layer(layer.media_type(), blob)
     }
 }
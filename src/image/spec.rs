@@ -1,10 +1,13 @@
 pub use super::go::{GoArch, GoOs};
-use std::collections::HashMap;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::collections::{BTreeSet, HashMap};
+use thiserror::Error;
 
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
 pub enum ImageSpecError {
-    #[fail(display = "JSON Error: {:?}", _0)]
+    #[error("JSON Error: {0:?}")]
     JsonError(serde_json::Error),
 }
 
@@ -24,15 +27,17 @@ pub enum ImageSpecError {
 /// >   used by the image, and provides history information for those layers.
 /// > * Changing it means creating a new derived image, instead of changing the
 /// >   existing image.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct ImageV1 {
-    /// A combinedChanging it means creating a new derived image, instead of changing the existing image.
+    /// A combined date and time at which the image was created, formatted as
     /// defined by RFC 3339, section 5.6.
-    created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
 
     /// Gives the name and/or email address of the person or entity which
     /// created and is responsible for maintaining the image.
-    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
 
     /// The CPU architecture which the binaries in this image are built to run
     /// on. Configurations SHOULD use, and implementations SHOULD understand,
@@ -47,15 +52,17 @@ pub struct ImageV1 {
     /// The execution parameters which SHOULD be used as a base when running a
     /// container using the image. This field can be null, in which case any
     /// execution parameters should be specified at creation of the container.
-    config: Option<ConfigV1>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConfigV1>,
 
     /// The rootfs key references the layer content addresses used by the image.
     /// This makes the image config hash depend on the filesystem hash.
-    rootfs: RootFSV1,
+    pub rootfs: RootFSV1,
 
     /// Describes the history of each layer. The array is ordered from first to
     /// last.
-    history: Option<Vec<HistoryV1>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<HistoryV1>>,
 }
 
 impl std::str::FromStr for ImageV1 {
@@ -66,10 +73,7 @@ impl std::str::FromStr for ImageV1 {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Empty {}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct ConfigV1 {
     /// The username or UID which is a platform-specific structure that allows
     /// specific control over which user the process run as. This acts as a
@@ -79,8 +83,8 @@ pub struct ConfigV1 {
     /// If `group`/`gid` is not specified, the default group and supplementary
     /// groups of the given `user`/`uid` in `/etc/passwd` from the container are
     /// applied.
-    #[serde(rename = "User")]
-    user: Option<String>,
+    #[serde(rename = "User", skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
 
     /// A set of ports to expose from a container running this image. Its keys
     /// can be in the format of: `port/tcp`, `port/udp`, `port` with the default
@@ -90,85 +94,129 @@ pub struct ConfigV1 {
     /// NOTE: This JSON structure value is unusual because it is a direct JSON
     /// serialization of the Go type `map[string]struct{}` and is represented in
     /// JSON as an object mapping its keys to an empty object.
-    #[serde(rename = "ExposedPorts")]
-    exposed_ports: Option<HashMap<String, Empty>>,
+    #[serde(
+        rename = "ExposedPorts",
+        default,
+        with = "go_set",
+        skip_serializing_if = "BTreeSet::is_empty"
+    )]
+    pub exposed_ports: BTreeSet<String>,
 
     /// Entries are in the format of VARNAME=VARVALUE. These values act as
     /// defaults and are merged with any specified when creating a container.
-    #[serde(rename = "Env")]
-    env: Option<Vec<String>>,
+    #[serde(rename = "Env", skip_serializing_if = "Option::is_none")]
+    pub env: Option<Vec<String>>,
 
     /// A list of arguments to use as the command to execute when the container
     /// starts. These values act as defaults and may be replaced by an
     /// entrypoint specified when creating a container.
-    #[serde(rename = "Entrypoint")]
-    entrypoint: Option<Vec<String>>,
+    #[serde(rename = "Entrypoint", skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<Vec<String>>,
 
     /// Default arguments to the entrypoint of the container. These values act
     /// as defaults and may be replaced by any specified when creating a
     /// container. If an Entrypoint value is not specified, then the first entry
     /// of the `Cmd` array SHOULD be interpreted as the executable to run.
-    #[serde(rename = "Cmd")]
-    cmd: Option<Vec<String>>,
+    #[serde(rename = "Cmd", skip_serializing_if = "Option::is_none")]
+    pub cmd: Option<Vec<String>>,
 
     /// A set of directories describing where the process is likely write data
     /// pecific to a container instance. NOTE: This JSON structure value is
     /// unusual because it is a direct JSON serialization of the Go type
     /// `map[string]struct{}` and is represented in JSON as an object mapping
     /// its keys to an empty object.
-    #[serde(rename = "Volumes")]
-    volumes: Option<HashMap<String, Empty>>,
+    #[serde(
+        rename = "Volumes",
+        default,
+        with = "go_set",
+        skip_serializing_if = "BTreeSet::is_empty"
+    )]
+    pub volumes: BTreeSet<String>,
 
     /// Sets the current working directory of the entrypoint process in the
     /// container. This value acts as a default and may be replaced by a working
     /// directory specified when creating a container.
-    #[serde(rename = "WorkingDir")]
-    working_dir: Option<String>,
+    #[serde(rename = "WorkingDir", skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
 
     /// The field contains arbitrary metadata for the container. This property
     /// MUST use the [annotation rules]
     ///
     /// [annotation rules]: https://github.com/opencontainers/image-spec/blob/master/annotations.md#rules.
-    #[serde(rename = "Labels")]
-    labels: Option<HashMap<String, String>>,
+    #[serde(rename = "Labels", skip_serializing_if = "Option::is_none")]
+    pub labels: Option<HashMap<String, String>>,
 
     /// The field contains the system call signal that will be sent to the
     /// container to exit. The signal can be a signal name in the format
     /// SIGNAME, for instance SIGKILL rSIGTMIN+3.
-    #[serde(rename = "StopSignal")]
-    stop_signal: Option<String>,
+    #[serde(rename = "StopSignal", skip_serializing_if = "Option::is_none")]
+    pub stop_signal: Option<String>,
+}
+
+/// (De)serialize a set of strings as the JSON serialization of the Go type
+/// `map[string]struct{}`, i.e. an object mapping its keys to empty objects.
+/// `null` is treated like an empty object.
+mod go_set {
+    use super::*;
+
+    pub fn serialize<S>(set: &BTreeSet<String>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Empty {}
+
+        serializer.collect_map(set.iter().map(|key| (key, Empty {})))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BTreeSet<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let map: Option<HashMap<String, IgnoredAny>> = Option::deserialize(deserializer)?;
+        Ok(map
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct RootFSV1 {
     /// MUST be set to `layers`. Implementations MUST generate an error if they
     /// encounter a unknown value while verifying or unpacking an image.
-    r#type: String,
+    pub r#type: String,
 
     /// An array of layer content hashes (DiffIDs), in order from first to last.
-    diff_ids: Vec<String>,
+    pub diff_ids: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct HistoryV1 {
     //// A combined date and time at which the layer was created, formatted as
     /// defined by RFC 3339, section 5.6.
-    created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
 
     /// The author of the build point.
-    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
 
     /// The command which created the layer.
-    created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
 
     /// A custom message set when creating the layer.
-    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
 
     /// This field is used to mark if the history item created a filesystem
     /// diff. It is set to true if this history item doesn't correspond to an
     /// actual layer in the rootfs section (for example, Dockerfile's ENV
     /// command results in no change to the filesystem).
-    empty_layer: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub empty_layer: Option<bool>,
 }
 
 #[cfg(test)]
@@ -185,5 +233,69 @@ mod tests {
 
         assert_eq!(image.architecture, GoArch::AMD64);
         assert_eq!(image.os, GoOs::Linux);
+        assert_eq!(
+            image.author.as_ref().map(String::as_str),
+            Some("Alyssa P. Hacker <alyspdev@example.com>")
+        );
+        assert_eq!(image.rootfs.diff_ids.len(), 2);
+        assert_eq!(image.history.as_ref().map(Vec::len), Some(2));
+
+        let config = image.config.expect("Image has no config");
+        assert_eq!(config.user.as_ref().map(String::as_str), Some("alice"));
+        assert_eq!(
+            config.exposed_ports.iter().collect::<Vec<_>>(),
+            vec!["8080/tcp"]
+        );
+        assert_eq!(config.env.as_ref().map(Vec::len), Some(3));
+        assert_eq!(
+            config.entrypoint,
+            Some(vec!["/bin/my-app-binary".to_owned()])
+        );
+        assert_eq!(config.cmd.as_ref().map(Vec::len), Some(3));
+        assert_eq!(
+            config.volumes.iter().collect::<Vec<_>>(),
+            vec!["/var/job-result-data", "/var/log/my-app-logs"]
+        );
+        assert_eq!(
+            config.working_dir.as_ref().map(String::as_str),
+            Some("/home/alice")
+        );
+        assert_eq!(
+            config
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get("com.example.project.git.url"))
+                .map(String::as_str),
+            Some("https://example.com/project.git")
+        );
+        assert_eq!(config.stop_signal, None);
+    }
+
+    #[test]
+    fn test_config_v1_roundtrip() {
+        let test_data = include_str!("test/config-v1.test.json");
+
+        let image: ImageV1 = test_data.parse().expect("Could not parse image config");
+        let serialized = serde_json::to_string(&image).expect("Could not serialize image config");
+        let reparsed: ImageV1 = serialized.parse().expect("Could not parse image config");
+
+        assert_eq!(image, reparsed);
+    }
+
+    #[test]
+    fn test_config_v1_null_fields() {
+        let image: ImageV1 = r#"{
+            "architecture": "amd64",
+            "os": "linux",
+            "config": { "ExposedPorts": null, "Volumes": null, "Cmd": ["sh"] },
+            "rootfs": { "type": "layers", "diff_ids": [] }
+        }"#
+        .parse()
+        .expect("Could not parse image config");
+
+        let config = image.config.expect("Image has no config");
+        assert!(config.exposed_ports.is_empty());
+        assert!(config.volumes.is_empty());
+        assert_eq!(config.cmd, Some(vec!["sh".to_owned()]));
     }
 }
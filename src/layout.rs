@@ -0,0 +1,235 @@
+use crate::image::manifest::{Digest, DigestMismatchError, ImageIndex};
+use crate::store::{ContentStore, FilesystemContentStore, StoreError};
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Version of the image layout written to and accepted from `oci-layout`.
+pub const IMAGE_LAYOUT_VERSION: &str = "1.0.0";
+
+#[derive(Debug, Error)]
+pub enum LayoutError {
+    #[error("I/O Error: {0:?}")]
+    IoError(#[source] io::Error),
+
+    #[error("JSON Error: {0:?}")]
+    JsonError(serde_json::Error),
+
+    #[error("Unsupported image layout version: {0}")]
+    UnsupportedVersion(String),
+
+    #[error("Blob not found: {0}")]
+    BlobNotFound(Digest),
+
+    #[error("{0}")]
+    DigestMismatch(#[from] DigestMismatchError),
+}
+
+impl From<StoreError> for LayoutError {
+    fn from(error: StoreError) -> Self {
+        match error {
+            StoreError::NotFound(digest) => LayoutError::BlobNotFound(digest),
+            StoreError::IoError(e) => LayoutError::IoError(e),
+            StoreError::DigestMismatch(e) => LayoutError::DigestMismatch(e),
+        }
+    }
+}
+
+/// Content of the `oci-layout` file.
+#[derive(Debug, Deserialize, Serialize)]
+struct ImageLayoutFile {
+    #[serde(rename = "imageLayoutVersion")]
+    version: String,
+}
+
+/// A directory in the [OCI Image
+/// Layout](https://github.com/opencontainers/image-spec/blob/master/image-layout.md)
+/// format.
+///
+/// Blobs are stored as `{root}/blobs/{algorithm}/{hex}`, the [ImageIndex] as
+/// `{root}/index.json`.
+#[derive(Debug)]
+pub struct OciImageLayout {
+    root: PathBuf,
+    blobs: FilesystemContentStore,
+}
+
+impl OciImageLayout {
+    /// Open an existing image layout.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, LayoutError> {
+        let root = path.as_ref().to_owned();
+
+        let layout = fs::read(root.join("oci-layout")).map_err(LayoutError::IoError)?;
+        let layout: ImageLayoutFile =
+            serde_json::from_slice(&layout).map_err(LayoutError::JsonError)?;
+
+        if layout.version != IMAGE_LAYOUT_VERSION {
+            return Err(LayoutError::UnsupportedVersion(layout.version));
+        }
+
+        Ok(OciImageLayout {
+            blobs: FilesystemContentStore::new(&root),
+            root,
+        })
+    }
+
+    /// Create a new, empty image layout.
+    ///
+    /// The directory is created if it does not exist yet.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, LayoutError> {
+        let layout = OciImageLayout {
+            root: path.as_ref().to_owned(),
+            blobs: FilesystemContentStore::new(&path),
+        };
+
+        fs::create_dir_all(layout.root.join("blobs")).map_err(LayoutError::IoError)?;
+
+        let version = ImageLayoutFile {
+            version: IMAGE_LAYOUT_VERSION.into(),
+        };
+        let version = serde_json::to_vec(&version).map_err(LayoutError::JsonError)?;
+        fs::write(layout.root.join("oci-layout"), version).map_err(LayoutError::IoError)?;
+
+        layout.put_index(&ImageIndex::default())?;
+
+        Ok(layout)
+    }
+
+    /// Return the root directory of the layout.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Read the image index.
+    pub fn get_index(&self) -> Result<ImageIndex, LayoutError> {
+        let index = fs::read(self.root.join("index.json")).map_err(LayoutError::IoError)?;
+        serde_json::from_slice(&index).map_err(LayoutError::JsonError)
+    }
+
+    /// Replace the image index.
+    pub fn put_index(&self, index: &ImageIndex) -> Result<(), LayoutError> {
+        let index = serde_json::to_vec(index).map_err(LayoutError::JsonError)?;
+        fs::write(self.root.join("index.json"), index).map_err(LayoutError::IoError)
+    }
+
+    /// Return true if the layout contains a blob.
+    pub fn has_blob(&self, digest: &Digest) -> bool {
+        self.blobs.exists(digest)
+    }
+
+    /// Open a blob for reading.
+    pub fn get_blob(&self, digest: &Digest) -> Result<impl Read, LayoutError> {
+        Ok(self.blobs.get(digest)?)
+    }
+
+    /// Add a blob to the layout.
+    ///
+    /// The content is verified against the digest before it is added.
+    pub fn add_blob<R: Read>(&self, digest: &Digest, mut reader: R) -> Result<(), LayoutError> {
+        Ok(self.blobs.put(digest, &mut reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::manifest::{DigestAlgorithm, ImageIndexEntry};
+    use crate::test_util::temp_dir;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_layout_create_open() {
+        let dir = temp_dir("layout-create");
+
+        OciImageLayout::create(&dir).expect("Could not create layout");
+        let layout = OciImageLayout::open(&dir).expect("Could not open layout");
+
+        let index = layout.get_index().expect("Could not read index");
+        assert_eq!(index, ImageIndex::default());
+
+        fs::write(dir.join("oci-layout"), r#"{"imageLayoutVersion": "2.0.0"}"#).unwrap();
+        match OciImageLayout::open(&dir) {
+            Err(LayoutError::UnsupportedVersion(version)) => assert_eq!(version, "2.0.0"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_layout_blobs() {
+        let dir = temp_dir("layout-blobs");
+        let layout = OciImageLayout::create(&dir).expect("Could not create layout");
+
+        let data = b"hello world";
+        let digest = Digest::compute(DigestAlgorithm::Sha256, data);
+        assert!(!layout.has_blob(&digest));
+
+        match layout.get_blob(&digest) {
+            Err(LayoutError::BlobNotFound(d)) => assert_eq!(d, digest),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => panic!("Missing blob was found"),
+        }
+
+        layout
+            .add_blob(&digest, &data[..])
+            .expect("Could not add blob");
+        assert!(layout.has_blob(&digest));
+        assert!(dir.join("blobs/sha256").join(&digest.hex).is_file());
+
+        let mut content = Vec::new();
+        layout
+            .get_blob(&digest)
+            .expect("Could not get blob")
+            .read_to_end(&mut content)
+            .expect("Could not read blob");
+        assert_eq!(&content[..], &data[..]);
+
+        let other = Digest::compute(DigestAlgorithm::Sha256, b"other");
+        match layout.add_blob(&other, &data[..]) {
+            Err(LayoutError::DigestMismatch(DigestMismatchError {
+                expected, actual, ..
+            })) => {
+                assert_eq!(expected, other);
+                assert_eq!(actual, digest);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(!layout.has_blob(&other));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_layout_index() {
+        let dir = temp_dir("layout-index");
+        let layout = OciImageLayout::create(&dir).expect("Could not create layout");
+
+        let manifest = br#"{"schemaVersion": 2}"#;
+        let digest = Digest::compute(DigestAlgorithm::Sha256, manifest);
+        layout
+            .add_blob(&digest, &manifest[..])
+            .expect("Could not add manifest");
+
+        let mut annotations = HashMap::new();
+        annotations.insert("org.opencontainers.image.ref.name".into(), "latest".into());
+
+        let mut index = layout.get_index().expect("Could not read index");
+        index.manifests.push(ImageIndexEntry {
+            media_type: "application/vnd.oci.image.manifest.v1+json".into(),
+            size: manifest.len(),
+            digest,
+            platform: None,
+            urls: None,
+            annotations: Some(annotations),
+        });
+        layout.put_index(&index).expect("Could not write index");
+
+        let layout = OciImageLayout::open(&dir).expect("Could not open layout");
+        assert_eq!(layout.get_index().expect("Could not read index"), index);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,3 @@
-#[macro_use]
-extern crate failure;
-
 #[macro_use]
 extern crate log;
 
@@ -13,8 +10,17 @@ extern crate serde_derive;
 pub mod distribution;
 pub use distribution::Registry;
 
+pub mod glue;
+
 pub mod image;
 pub use image::Image;
 
+pub mod layout;
+
 pub mod runtime;
 pub use runtime::{Bundle, Runtime};
+
+pub mod store;
+
+#[cfg(test)]
+mod test_util;
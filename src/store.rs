@@ -0,0 +1,168 @@
+use crate::image::manifest::{Digest, DigestMismatchError};
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("Blob not found: {0}")]
+    NotFound(Digest),
+
+    #[error("I/O Error: {0:?}")]
+    IoError(#[source] io::Error),
+
+    #[error("{0}")]
+    DigestMismatch(#[from] DigestMismatchError),
+}
+
+/// Storage for content-addressed blobs.
+pub trait ContentStore: Send + Sync {
+    /// Open a blob for reading.
+    fn get(&self, digest: &Digest) -> Result<Box<dyn Read>, StoreError>;
+
+    /// Add a blob to the store.
+    ///
+    /// Implementations must verify the content against the digest before
+    /// making it available.
+    fn put(&self, digest: &Digest, reader: &mut dyn Read) -> Result<(), StoreError>;
+
+    /// Return true if the store contains a blob.
+    fn exists(&self, digest: &Digest) -> bool;
+
+    /// Remove a blob from the store.
+    fn delete(&self, digest: &Digest) -> Result<(), StoreError>;
+}
+
+/// Content store keeping blobs on the filesystem.
+///
+/// Blobs are stored as `{root}/blobs/{algorithm}/{hex}`, as in the [OCI Image
+/// Layout](https://github.com/opencontainers/image-spec/blob/master/image-layout.md).
+#[derive(Debug)]
+pub struct FilesystemContentStore {
+    root: PathBuf,
+}
+
+impl FilesystemContentStore {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        FilesystemContentStore {
+            root: root.as_ref().to_owned(),
+        }
+    }
+
+    fn blob_path(&self, digest: &Digest) -> PathBuf {
+        self.root
+            .join("blobs")
+            .join(digest.algorithm.to_string())
+            .join(&digest.hex)
+    }
+}
+
+impl ContentStore for FilesystemContentStore {
+    fn get(&self, digest: &Digest) -> Result<Box<dyn Read>, StoreError> {
+        match File::open(self.blob_path(digest)) {
+            Ok(file) => Ok(Box::new(file)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(StoreError::NotFound(digest.clone()))
+            }
+            Err(e) => Err(StoreError::IoError(e)),
+        }
+    }
+
+    fn put(&self, digest: &Digest, reader: &mut dyn Read) -> Result<(), StoreError> {
+        let path = self.blob_path(digest);
+        let partial = path.with_file_name(format!("{}.partial", digest.hex));
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(StoreError::IoError)?;
+        }
+
+        let mut reader = digest
+            .verify_reader(reader)
+            .map_err(StoreError::DigestMismatch)?;
+        let mut file = File::create(&partial).map_err(StoreError::IoError)?;
+        io::copy(&mut reader, &mut file).map_err(StoreError::IoError)?;
+        drop(file);
+
+        if let Err(e) = reader.finish() {
+            fs::remove_file(&partial).map_err(StoreError::IoError)?;
+            return Err(StoreError::DigestMismatch(e));
+        }
+
+        fs::rename(&partial, &path).map_err(StoreError::IoError)
+    }
+
+    fn exists(&self, digest: &Digest) -> bool {
+        self.blob_path(digest).is_file()
+    }
+
+    fn delete(&self, digest: &Digest) -> Result<(), StoreError> {
+        match fs::remove_file(self.blob_path(digest)) {
+            Ok(()) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(StoreError::NotFound(digest.clone()))
+            }
+            Err(e) => Err(StoreError::IoError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::manifest::DigestAlgorithm;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_filesystem_content_store() {
+        let dir = temp_dir("store-roundtrip");
+        let store = FilesystemContentStore::new(&dir);
+
+        let data = b"hello world";
+        let digest = Digest::compute(DigestAlgorithm::Sha256, data);
+        assert!(!store.exists(&digest));
+
+        store
+            .put(&digest, &mut &data[..])
+            .expect("Could not put blob");
+        assert!(store.exists(&digest));
+        assert!(dir.join("blobs/sha256").join(&digest.hex).is_file());
+
+        let mut content = Vec::new();
+        store
+            .get(&digest)
+            .expect("Could not get blob")
+            .read_to_end(&mut content)
+            .expect("Could not read blob");
+        assert_eq!(&content[..], &data[..]);
+
+        store.delete(&digest).expect("Could not delete blob");
+        assert!(!store.exists(&digest));
+
+        match store.get(&digest) {
+            Err(StoreError::NotFound(d)) => assert_eq!(d, digest),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => panic!("Deleted blob was found"),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_filesystem_content_store_digest_mismatch() {
+        let dir = temp_dir("store-mismatch");
+        let store = FilesystemContentStore::new(&dir);
+
+        let digest = Digest::compute(DigestAlgorithm::Sha256, b"expected");
+        match store.put(&digest, &mut &b"tampered"[..]) {
+            Err(StoreError::DigestMismatch(DigestMismatchError { expected, .. })) => {
+                assert_eq!(expected, digest)
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(!store.exists(&digest));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,17 @@
+//! Helpers shared by the unit tests of the crate.
+
+use std::path::PathBuf;
+
+/// Return a directory path for the test `name` in the temporary directory.
+///
+/// The path is unique to this process, and anything left there by an earlier
+/// run is removed. The directory itself is not created.
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "opencontainers-test-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}